@@ -0,0 +1,61 @@
+//! Convert between a directory of .jhf fonts and a font pack.
+//!
+//! ```text
+//! cargo run --example jhf_pack -- pack fonts/ fonts.hpk
+//! cargo run --example jhf_pack -- unpack fonts.hpk fonts/
+//! ```
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+use hershey_reader::HersheyFont;
+use hershey_reader::pack::{to_pack, Pack, PackFont};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+	let args = std::env::args().skip(1).collect::<Vec<_>>();
+	
+	match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+		["pack", dir, out] => {
+			let mut fonts = Vec::new();
+			for entry in fs::read_dir(dir)? {
+				let path = entry?.path();
+				if path.extension().and_then(OsStr::to_str) != Some("jhf") { continue; }
+				
+				let name = path.file_name().unwrap().to_string_lossy().into_owned();
+				let font = HersheyFont::new_from_str(name, &fs::read_to_string(&path)?)
+					.map_err(|e| format!("{}: {:?}", path.display(), e))?;
+				fonts.push(font);
+			}
+			// so the same fonts always make the same file
+			fonts.sort_by(|a, b| a.name.cmp(&b.name));
+			
+			let bytes = to_pack(&fonts.iter().map(PackFont::from).collect::<Vec<_>>())
+				.map_err(|e| format!("{}: {:?}", out, e))?;
+			fs::write(out, &bytes)?;
+			println!("Packed {} fonts into `{}` ({} bytes).", fonts.len(), out, bytes.len());
+		}
+		["unpack", pack, dir] => {
+			let bytes = fs::read(pack)?;
+			let pack = Pack::new(&bytes).map_err(|e| format!("{}: {:?}", pack, e))?;
+			
+			fs::create_dir_all(dir)?;
+			for font in pack.fonts() {
+				let font = font.to_font();
+				let jhf = font.to_jhf().map_err(|e| format!("{}: {:?}", font.name, e))?;
+				
+				// the name's usually the file name, but don't go writing anywhere else
+				let name = Path::new(&font.name).file_name().unwrap_or_else(|| OsStr::new("unnamed.jhf"));
+				fs::write(Path::new(dir).join(name), jhf)?;
+			}
+			println!("Unpacked {} fonts into `{}`.", pack.len(), dir);
+		}
+		_ => {
+			eprintln!("usage: jhf_pack pack <dir of .jhf> <out.hpk>");
+			eprintln!("       jhf_pack unpack <in.hpk> <out dir>");
+			std::process::exit(1);
+		}
+	}
+	
+	Ok(())
+}
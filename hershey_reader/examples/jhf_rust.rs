@@ -0,0 +1,53 @@
+//! Generate Rust tables for a .jhf font, or a directory of them, on stdout.
+//!
+//! ```text
+//! cargo run --example jhf_rust -- fonts/futural.jhf > src/futural.rs
+//! cargo run --example jhf_rust -- fonts/ "0123456789.-" > src/fonts.rs
+//! ```
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+use hershey_reader::HersheyFont;
+use hershey_reader::codegen::{to_rust, to_rust_fonts, RustOptions};
+
+fn read(path: &Path) -> Result<HersheyFont, Box<dyn std::error::Error>> {
+	let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+	let font = HersheyFont::new_from_str(name, &fs::read_to_string(path)?)
+		.map_err(|e| format!("{}: {:?}", path.display(), e))?;
+	Ok(font)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+	let args = std::env::args().skip(1).collect::<Vec<_>>();
+	let (path, subset) = match &args[..] {
+		[path] => (Path::new(path), None),
+		[path, subset] => (Path::new(path), Some(subset.as_str())),
+		_ => {
+			eprintln!("usage: jhf_rust <font.jhf or dir> [characters to keep]");
+			std::process::exit(1);
+		}
+	};
+	let opts = RustOptions { subset };
+	
+	if path.is_dir() {
+		let mut fonts = Vec::new();
+		for entry in fs::read_dir(path)? {
+			let path = entry?.path();
+			if path.extension().and_then(OsStr::to_str) == Some("jhf") {
+				fonts.push(read(&path)?);
+			}
+		}
+		fonts.sort_by(|a, b| a.name.cmp(&b.name));
+		
+		print!("{}", to_rust_fonts(&fonts, &opts));
+	} else {
+		let mut font = read(path)?;
+		// the name ends up in the doc comment, and the file name's not much use there
+		font.name = font.name.trim_end_matches(".jhf").to_owned();
+		print!("{}", to_rust(&font, &opts));
+	}
+	
+	Ok(())
+}
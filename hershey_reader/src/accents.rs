@@ -0,0 +1,338 @@
+//! Accented letters, put together from a plain letter and a mark. None of
+//! the bundled fonts have é or ü, but they all have e and u, so
+//! [`HersheyFont::with_accents`] can make them.
+//!
+//! The marks are small strokes drawn here, apart from the dots (¨ and ˙),
+//! which are the font's own full stop so they come out as heavy as the rest
+//! of the font. Letters with bars through them (ø, đ, ł) and ligatures
+//! (æ, ß) aren't made out of anything, so they aren't covered.
+//!
+//! Some get their mark drawn the way it's printed rather than the way it's
+//! named: the caron on ď, ť, Ľ and ľ is an apostrophe beside the stem, the
+//! cedilla on ģ is a turned comma on top, and on Ģ, Ķ, Ļ, Ņ, Ŗ (and the
+//! lowercase ones) it's a comma underneath.
+
+use std::convert::TryFrom;
+
+use crate::{HersheyChar, HersheyFont};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+	Acute,
+	Grave,
+	Circumflex,
+	Caron,
+	Tilde,
+	Macron,
+	Breve,
+	Ring,
+	Diaeresis,
+	DotAbove,
+	DoubleAcute,
+	Cedilla,
+	Ogonek,
+}
+
+/// What goes on what. Each run is the accented letters, then the plain
+/// letters they're made from, in the same order.
+const COMPOSED: &[(Mark, &str, &str)] = &[
+	(Mark::Acute, "ÁÉÍÓÚÝáéíóúýĆćĹĺŃńŔŕŚśŹź", "AEIOUYaeiouyCcLlNnRrSsZz"),
+	(Mark::Grave, "ÀÈÌÒÙàèìòù", "AEIOUaeiou"),
+	(Mark::Circumflex, "ÂÊÎÔÛâêîôûĈĉĜĝĤĥĴĵŜŝŴŵŶŷ", "AEIOUaeiouCcGgHhJjSsWwYy"),
+	(Mark::Caron, "ČčĎďĚěĽľŇňŘřŠšŤťŽž", "CcDdEeLlNnRrSsTtZz"),
+	(Mark::Tilde, "ÃÑÕãñõĨĩŨũ", "ANOanoIiUu"),
+	(Mark::Macron, "ĀāĒēĪīŌōŪū", "AaEeIiOoUu"),
+	(Mark::Breve, "ĂăĔĕĞğĬĭŎŏŬŭ", "AaEeGgIiOoUu"),
+	(Mark::Ring, "ÅåŮů", "AaUu"),
+	(Mark::Diaeresis, "ÄËÏÖÜäëïöüÿŸ", "AEIOUaeiouyY"),
+	(Mark::DotAbove, "ĊċĖėĠġİŻż", "CcEeGgIZz"),
+	(Mark::DoubleAcute, "ŐőŰű", "OoUu"),
+	(Mark::Cedilla, "ÇçŞşŢţĢģĶķĻļŅņŖŗ", "CcSsTtGgKkLlNnRr"),
+	(Mark::Ogonek, "ĄąĘęĮįŲų", "AaEeIiUu"),
+];
+
+/// The plain letter and mark that `c` is made of, if it's one this knows.
+pub fn decompose(c: char) -> Option<(char, Mark)> {
+	COMPOSED.iter().find_map(|&(mark, composed, bases)| {
+		let i = composed.chars().position(|x| x == c)?;
+		Some((bases.chars().nth(i)?, mark))
+	})
+}
+
+/// Every character [`decompose`] knows.
+pub fn composable() -> impl Iterator<Item = char> {
+	COMPOSED.iter().flat_map(|&(_, composed, _)| composed.chars())
+}
+
+/// Space between the top of the letter and the bottom of a mark.
+const GAP: i32 = 2;
+
+type Strokes = &'static [&'static [(i8, i8)]];
+
+/// Hanging down from y = 0, with the top on x = 0.
+const COMMA: Strokes = &[&[(0, 0), (0, 2), (-1, 4)]];
+
+/// [`COMMA`] upside down, sitting on y = 0.
+const TURNED_COMMA: Strokes = &[&[(0, 0), (0, -2), (1, -4)]];
+
+impl Mark {
+	/// Whether it goes on top of the letter. The rest hang off the bottom.
+	pub fn is_above(self) -> bool {
+		!matches!(self, Mark::Cedilla | Mark::Ogonek)
+	}
+	
+	/// Strokes centred on x = 0, sitting on y = 0 if it goes above, or
+	/// hanging from it if it goes below. Dots are drawn separately.
+	fn strokes(self) -> Strokes {
+		match self {
+			Mark::Acute => &[&[(-1, 0), (1, -4)]],
+			Mark::Grave => &[&[(1, 0), (-1, -4)]],
+			Mark::Circumflex => &[&[(-3, 0), (0, -3), (3, 0)]],
+			Mark::Caron => &[&[(-3, -3), (0, 0), (3, -3)]],
+			Mark::Tilde => &[&[(-4, -1), (-3, -2), (-2, -2), (0, -1), (2, 0), (3, 0), (4, -1)]],
+			Mark::Macron => &[&[(-4, -1), (4, -1)]],
+			Mark::Breve => &[&[(-3, -3), (-2, -1), (0, 0), (2, -1), (3, -3)]],
+			Mark::Ring => &[&[(-1, 0), (1, 0), (2, -1), (2, -3), (1, -4), (-1, -4), (-2, -3), (-2, -1), (-1, 0)]],
+			Mark::DoubleAcute => &[&[(-3, 0), (-1, -4)], &[(1, 0), (3, -4)]],
+			Mark::Cedilla => &[&[(0, 0), (0, 1), (2, 2), (2, 3), (1, 4), (-1, 4)]],
+			Mark::Ogonek => &[&[(0, 0), (-2, 2), (-2, 3), (-1, 4), (1, 4)]],
+			Mark::Diaeresis | Mark::DotAbove => &[],
+		}
+	}
+	
+	/// Where the dots go, if it's made of dots.
+	fn dots(self) -> &'static [i32] {
+		match self {
+			Mark::Diaeresis => &[-3, 3],
+			Mark::DotAbove => &[0],
+			_ => &[],
+		}
+	}
+}
+
+/// The smallest box around every point in `vertices`, as
+/// `(min_x, min_y, max_x, max_y)`.
+fn ink(vertices: &[Option<(i8, i8)>]) -> Option<(i32, i32, i32, i32)> {
+	let mut points = vertices.iter().flatten().map(|&(x, y)| (x as i32, y as i32));
+	let first = points.next()?;
+	Some(points.fold((first.0, first.1, first.0, first.1), |(x0, y0, x1, y1), (x, y)| {
+		(x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+	}))
+}
+
+/// `vertices` without the dot on top, for i and j. That's anything that
+/// sits wholly above the top of the stem.
+fn dotless(vertices: &[Option<(i8, i8)>]) -> Vec<Option<(i8, i8)>> {
+	let strokes: Vec<_> = vertices.split(Option::is_none).filter(|s| !s.is_empty()).collect();
+	let stem_top = strokes.iter()
+		.filter_map(|s| ink(s))
+		.filter(|&(_, y0, _, y1)| y1 - y0 > 3)
+		.map(|(_, y0, _, _)| y0)
+		.min();
+	
+	let mut kept = Vec::new();
+	for s in strokes {
+		if matches!((ink(s), stem_top), (Some((_, _, _, bottom)), Some(top)) if bottom < top) {
+			continue;
+		}
+		if !kept.is_empty() { kept.push(None); }
+		kept.extend_from_slice(s);
+	}
+	kept
+}
+
+/// Add a stroke to `vertices`, moved by `(dx, dy)`. `None` if that goes
+/// somewhere an `i8` can't.
+fn push_stroke(vertices: &mut Vec<Option<(i8, i8)>>, stroke: impl IntoIterator<Item = (i8, i8)>, (dx, dy): (i32, i32)) -> Option<()> {
+	if !vertices.is_empty() { vertices.push(None); }
+	for (x, y) in stroke {
+		let x = i8::try_from(x as i32 + dx).ok()?;
+		let y = i8::try_from(y as i32 + dy).ok()?;
+		vertices.push(Some((x, y)));
+	}
+	Some(())
+}
+
+impl HersheyFont {
+	/// Make a glyph for `c` out of its plain letter and mark, if
+	/// [`decompose`] knows it and the font has the letter.
+	pub fn compose(&self, c: char) -> Option<HersheyChar> {
+		let (base, mark) = decompose(c)?;
+		let base = self.glyph_for(base)?;
+		
+		let mut vertex_data = match c {
+			'í' | 'ì' | 'î' | 'ĩ' | 'ī' | 'ĭ' | 'ï' | 'ĵ' => dotless(&base.vertex_data),
+			_ => base.vertex_data.clone(),
+		};
+		let (x0, y0, x1, y1) = ink(&vertex_data)?;
+		let centre = (x0 + x1) / 2;
+		
+		let (strokes, (x, y)) = match c {
+			'ď' | 'ť' | 'Ľ' | 'ľ' => {
+				// just right of the top of the tallest stem
+				let stem = vertex_data.iter().flatten()
+					.filter(|v| v.1 as i32 == y0)
+					.map(|v| v.0 as i32)
+					.max()?;
+				(COMMA, (stem + GAP, y0))
+			}
+			'ģ' => (TURNED_COMMA, (centre, y0 - GAP)),
+			'Ģ' | 'Ķ' | 'ķ' | 'Ļ' | 'ļ' | 'Ņ' | 'ņ' | 'Ŗ' | 'ŗ' => (COMMA, (centre, y1 + GAP)),
+			_ if mark.is_above() => (mark.strokes(), (centre, y0 - GAP)),
+			_ if mark == Mark::Ogonek => (mark.strokes(), (x1 - 2, y1)),
+			_ => (mark.strokes(), (centre, y1)),
+		};
+		
+		for stroke in strokes {
+			push_stroke(&mut vertex_data, stroke.iter().copied(), (x, y))?;
+		}
+		
+		// the apostrophe can stick out past the letter, so make room for it
+		let right_hand = match c {
+			'ď' | 'ť' | 'Ľ' | 'ľ' => i8::try_from(ink(&vertex_data)?.2 + GAP).ok()?.max(base.right_hand),
+			_ => base.right_hand,
+		};
+		
+		if !mark.dots().is_empty() {
+			// the font's own full stop, if it has a sensibly small one
+			let stop = self.glyph_for('.')
+				.filter(|stop| ink(&stop.vertex_data).is_some_and(|(x0, y0, x1, y1)| x1 - x0 <= 4 && y1 - y0 <= 4));
+			
+			for dx in mark.dots() {
+				match stop {
+					Some(stop) => {
+						let (sx0, _, sx1, sy1) = ink(&stop.vertex_data)?;
+						let offset = (x + dx - (sx0 + sx1) / 2, y - sy1);
+						for run in stop.vertex_data.split(Option::is_none).filter(|s| !s.is_empty()) {
+							push_stroke(&mut vertex_data, run.iter().flatten().copied(), offset)?;
+						}
+					}
+					None => push_stroke(&mut vertex_data, [(0, 0), (0, -1)], (x + dx, y))?,
+				}
+			}
+		}
+		
+		Some(HersheyChar {
+			vertex_num: vertex_data.len(),
+			vertex_data,
+			right_hand,
+			..base.clone()
+		})
+	}
+	
+	/// Add every accented letter [`compose`](Self::compose) can make, and
+	/// doesn't already have, to the end of [`chars`](Self::chars). Fonts in
+	/// ASCII order get a [`unicode`](Self::unicode) map so the new ones can
+	/// be found, which means control characters don't get the space anymore.
+	pub fn with_accents(mut self) -> Self {
+		let composed: Vec<_> = composable()
+			.filter(|&c| self.glyph_for(c).is_none())
+			.filter_map(|c| Some((c, self.compose(c)?)))
+			.collect();
+		if composed.is_empty() { return self; }
+		
+		let mut map = match self.unicode.take() {
+			Some(map) => map,
+			None => (' '..='~').map(Some).collect(),
+		};
+		map.resize(self.chars.len(), None);
+		
+		for (c, chr) in composed {
+			map.push(Some(c));
+			self.chars.push(chr);
+		}
+		
+		self.unicode = Some(map);
+		self
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	
+	#[test]
+	fn makes_accents() {
+		assert_eq!(decompose('é'), Some(('e', Mark::Acute)));
+		assert_eq!(decompose('ų'), Some(('u', Mark::Ogonek)));
+		assert_eq!(decompose('e'), None);
+		
+		let font = test_font("futural");
+		let e = font.glyph_for('e').unwrap();
+		let e_acute = font.compose('é').unwrap();
+		assert_eq!(e_acute.vertex_data[..e.vertex_data.len()], e.vertex_data[..]);
+		assert_eq!(e_acute.advance(), e.advance());
+		assert_eq!(e_acute.strokes().len(), e.strokes().len() + 1);
+		
+		// above the e, and centred on it
+		let (x0, y0, x1, _) = ink(&e.vertex_data).unwrap();
+		let acute = e_acute.strokes().pop().unwrap();
+		assert!(acute.iter().all(|&(_, y)| y < y0 as f64));
+		assert_eq!((acute[0].0 + acute[1].0) / 2.0, ((x0 + x1) / 2) as f64);
+		
+		// no dot on the i, under the accent
+		let i = font.glyph_for('i').unwrap();
+		assert_eq!(font.compose('í').unwrap().strokes().len(), i.strokes().len());
+		
+		// the cedilla hangs off the bottom
+		let c = font.glyph_for('c').unwrap();
+		let (_, _, _, bottom) = ink(&c.vertex_data).unwrap();
+		assert!(font.compose('ç').unwrap().strokes().pop().unwrap().iter().all(|&(_, y)| y >= bottom as f64));
+		
+		// two full stops, sitting just over the u
+		let u = font.glyph_for('u').unwrap();
+		let (_, top, _, _) = ink(&u.vertex_data).unwrap();
+		let u_umlaut = font.compose('ü').unwrap();
+		let dots: Vec<_> = u_umlaut.vertex_data[u.vertex_data.len() + 1..]
+			.split(Option::is_none)
+			.map(|dot| ink(dot).unwrap())
+			.collect();
+		let stop = ink(&font.glyph_for('.').unwrap().vertex_data).unwrap();
+		assert_eq!(dots.len(), 2);
+		assert!(dots.iter().all(|&(x0, y0, x1, y1)| y1 == top - GAP && (x1 - x0, y1 - y0) == (stop.2 - stop.0, stop.3 - stop.1)));
+		assert_eq!(dots[1].0 - dots[0].0, 6);
+	}
+	
+	#[test]
+	fn commas_and_apostrophes() {
+		assert_eq!(decompose('ď'), Some(('d', Mark::Caron)));
+		assert_eq!(decompose('ģ'), Some(('g', Mark::Cedilla)));
+		
+		let font = test_font("futural");
+		let ink_of = |c: char| ink(&font.glyph_for(c).unwrap().vertex_data).unwrap();
+		let mark_of = |c: char| font.compose(c).unwrap().strokes().pop().unwrap();
+		
+		// beside the top of the d, with room made for it
+		let (_, top, right, _) = ink_of('d');
+		let d_caron = font.compose('ď').unwrap();
+		assert!(mark_of('ď').iter().all(|&(x, y)| x > right as f64 && y >= top as f64));
+		assert!(ink(&d_caron.vertex_data).unwrap().2 < d_caron.right_hand as i32);
+		
+		// under the n, and over the g since there's a tail in the way
+		let (_, _, _, bottom) = ink_of('n');
+		assert!(mark_of('ņ').iter().all(|&(_, y)| y >= (bottom + GAP) as f64));
+		let (_, top, _, _) = ink_of('g');
+		assert!(mark_of('ģ').iter().all(|&(_, y)| y < top as f64));
+		assert_eq!(font.compose('ģ').unwrap().advance(), font.glyph_for('g').unwrap().advance());
+	}
+	
+	#[test]
+	fn adds_them_to_fonts() {
+		let plain = test_font("timesr");
+		let font = plain.clone().with_accents();
+		
+		assert_eq!(font.chars.len(), plain.chars.len() + composable().count());
+		assert_eq!(font.glyph_for('a'), plain.glyph_for('a'));
+		assert_eq!(font.glyph_for('Ŵ'), plain.compose('Ŵ').as_ref());
+		let (_, n_tilde) = font.char_map().into_iter().find(|&(c, _)| c == 'ñ').unwrap();
+		assert!(n_tilde >= plain.chars.len());
+		
+		// still reads back in after a trip through .jhf
+		assert!(font.chars.iter().all(|chr| chr.to_jhf().is_ok()));
+		
+		// nothing to put accents on
+		let greek = test_font("greek").with_builtin_unicode();
+		assert_eq!(greek.clone().with_accents(), greek);
+	}
+}
@@ -0,0 +1,322 @@
+//! Fitting smooth cubic Béziers to strokes, mostly following Philip Schneider's
+//! "An Algorithm for Automatically Fitting Digitized Curves" (Graphics Gems, 1990).
+
+use std::fmt::Write;
+
+use crate::{HersheyChar, Point};
+
+/// Start point, two control points, end point.
+pub type Cubic = [Point; 4];
+
+/// A stroke as a run of cubics, each one starting where the last one ended.
+pub type CurveStroke = Vec<Cubic>;
+
+/// A character with its strokes fitted to curves.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CurveGlyph {
+	pub left_hand: f64,
+	pub right_hand: f64,
+	pub strokes: Vec<CurveStroke>,
+}
+
+impl CurveGlyph {
+	/// Fit every stroke of `chr`. See [`fit_stroke`] for the knobs.
+	pub fn new(chr: &HersheyChar, tolerance: f64, corner_angle: f64) -> Self {
+		CurveGlyph {
+			left_hand: chr.left_hand as f64,
+			right_hand: chr.right_hand as f64,
+			strokes: chr.strokes()
+				.iter()
+				.map(|s| fit_stroke(s, tolerance, corner_angle))
+				.filter(|s| !s.is_empty())
+				.collect(),
+		}
+	}
+	
+	/// SVG path data (`M`/`C` commands) for all the strokes, in font units.
+	pub fn svg_path(&self) -> String {
+		svg_path(&self.strokes)
+	}
+}
+
+/// SVG path data (`M`/`C` commands) for some curves.
+pub fn svg_path(strokes: &[CurveStroke]) -> String {
+	let mut d = String::new();
+	
+	for stroke in strokes {
+		let first = match stroke.first() {
+			Some(c) => c[0],
+			None => continue,
+		};
+		
+		if !d.is_empty() { d.push(' '); }
+		write!(d, "M{} {}", num(first.0), num(first.1)).unwrap();
+		for [_, c1, c2, end] in stroke {
+			write!(d, " C{} {} {} {} {} {}",
+				num(c1.0), num(c1.1),
+				num(c2.0), num(c2.1),
+				num(end.0), num(end.1),
+			).unwrap();
+		}
+	}
+	
+	d
+}
+
+/// Short number formatting: 3 decimals at most, and no `-0`.
+pub(crate) fn num(v: f64) -> f64 {
+	(v * 1000.0).round() / 1000.0 + 0.0
+}
+
+fn add(a: Point, b: Point) -> Point { (a.0 + b.0, a.1 + b.1) }
+fn sub(a: Point, b: Point) -> Point { (a.0 - b.0, a.1 - b.1) }
+fn mul(a: Point, s: f64) -> Point { (a.0 * s, a.1 * s) }
+fn dot(a: Point, b: Point) -> f64 { a.0 * b.0 + a.1 * b.1 }
+fn len(a: Point) -> f64 { a.0.hypot(a.1) }
+
+fn normalize(a: Point) -> Point {
+	let l = len(a);
+	if l == 0.0 { a } else { mul(a, 1.0 / l) }
+}
+
+/// Point on `c` at `t`.
+pub fn eval(c: &Cubic, t: f64) -> Point {
+	let mt = 1.0 - t;
+	let (a, b, cc, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+	(
+		a * c[0].0 + b * c[1].0 + cc * c[2].0 + d * c[3].0,
+		a * c[0].1 + b * c[1].1 + cc * c[2].1 + d * c[3].1,
+	)
+}
+
+fn derivative(c: &Cubic, t: f64) -> Point {
+	let mt = 1.0 - t;
+	let d0 = mul(sub(c[1], c[0]), 3.0 * mt * mt);
+	let d1 = mul(sub(c[2], c[1]), 6.0 * mt * t);
+	let d2 = mul(sub(c[3], c[2]), 3.0 * t * t);
+	add(add(d0, d1), d2)
+}
+
+fn second_derivative(c: &Cubic, t: f64) -> Point {
+	let a = mul(add(sub(c[2], mul(c[1], 2.0)), c[0]), 6.0 * (1.0 - t));
+	let b = mul(add(sub(c[3], mul(c[2], 2.0)), c[1]), 6.0 * t);
+	add(a, b)
+}
+
+/// Fit `stroke` with as few cubics as it takes to stay within `tolerance`
+/// of every point. Anywhere the stroke turns sharper than `corner_angle`
+/// (in radians) stays a sharp corner instead of getting rounded off.
+///
+/// Strokes that end where they start get a smooth join there too,
+/// unless that's a corner as well.
+pub fn fit_stroke(stroke: &[Point], tolerance: f64, corner_angle: f64) -> CurveStroke {
+	let mut points = stroke.to_vec();
+	points.dedup();
+	if points.len() < 2 { return Vec::new(); }
+	
+	let turn = |a: Point, b: Point, c: Point| {
+		let (u, v) = (normalize(sub(b, a)), normalize(sub(c, b)));
+		dot(u, v).clamp(-1.0, 1.0).acos()
+	};
+	
+	let n = points.len();
+	let closed = n > 3 && points[0] == points[n - 1];
+	let smooth_loop = closed && turn(points[n - 2], points[0], points[1]) <= corner_angle;
+	
+	let mut corners = vec![0];
+	corners.extend((1..n - 1).filter(|&i| turn(points[i - 1], points[i], points[i + 1]) > corner_angle));
+	corners.push(n - 1);
+	
+	let mut curves = Vec::new();
+	for pair in corners.windows(2) {
+		let piece = &points[pair[0]..=pair[1]];
+		
+		let mut start_tangent = normalize(sub(piece[1], piece[0]));
+		let mut end_tangent = normalize(sub(piece[piece.len() - 2], piece[piece.len() - 1]));
+		if smooth_loop && pair[0] == 0 {
+			start_tangent = normalize(sub(points[1], points[n - 2]));
+		}
+		if smooth_loop && pair[1] == n - 1 {
+			end_tangent = normalize(sub(points[n - 2], points[1]));
+		}
+		
+		fit_cubics(piece, start_tangent, end_tangent, tolerance, &mut curves);
+	}
+	
+	curves
+}
+
+fn fit_cubics(points: &[Point], t1: Point, t2: Point, tolerance: f64, out: &mut Vec<Cubic>) {
+	let (first, last) = (points[0], points[points.len() - 1]);
+	
+	if points.len() == 2 {
+		let d = len(sub(last, first)) / 3.0;
+		out.push([first, add(first, mul(t1, d)), add(last, mul(t2, d)), last]);
+		return;
+	}
+	
+	let mut u = chord_length(points);
+	let mut curve = least_squares(points, &u, t1, t2);
+	let (mut error, mut split) = max_error(points, &curve, &u);
+	if error <= tolerance {
+		out.push(curve);
+		return;
+	}
+	
+	// Close-ish: try nudging the parameters before giving up and splitting.
+	if error <= tolerance * 4.0 {
+		for _ in 0..4 {
+			u = reparameterize(points, &u, &curve);
+			curve = least_squares(points, &u, t1, t2);
+			let (e, s) = max_error(points, &curve, &u);
+			error = e;
+			split = s;
+			if error <= tolerance {
+				out.push(curve);
+				return;
+			}
+		}
+	}
+	
+	let center = normalize(sub(points[split - 1], points[split + 1]));
+	fit_cubics(&points[..=split], t1, center, tolerance, out);
+	fit_cubics(&points[split..], mul(center, -1.0), t2, tolerance, out);
+}
+
+fn chord_length(points: &[Point]) -> Vec<f64> {
+	let mut u = vec![0.0];
+	for pair in points.windows(2) {
+		u.push(u[u.len() - 1] + len(sub(pair[1], pair[0])));
+	}
+	
+	let total = u[u.len() - 1];
+	u.iter().map(|x| x / total).collect()
+}
+
+/// Best control points for fixed end tangents `t1` and `t2`.
+fn least_squares(points: &[Point], u: &[f64], t1: Point, t2: Point) -> Cubic {
+	let (first, last) = (points[0], points[points.len() - 1]);
+	
+	let mut c = [[0.0; 2]; 2];
+	let mut x = [0.0; 2];
+	
+	for (&p, &t) in points.iter().zip(u) {
+		let mt = 1.0 - t;
+		let (b0, b1, b2, b3) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+		let a1 = mul(t1, b1);
+		let a2 = mul(t2, b2);
+		
+		c[0][0] += dot(a1, a1);
+		c[0][1] += dot(a1, a2);
+		c[1][1] += dot(a2, a2);
+		
+		let tmp = sub(p, add(mul(first, b0 + b1), mul(last, b2 + b3)));
+		x[0] += dot(a1, tmp);
+		x[1] += dot(a2, tmp);
+	}
+	c[1][0] = c[0][1];
+	
+	let det = c[0][0] * c[1][1] - c[1][0] * c[0][1];
+	let (mut alpha1, mut alpha2) = if det.abs() > 1e-12 {
+		(
+			(x[0] * c[1][1] - x[1] * c[0][1]) / det,
+			(c[0][0] * x[1] - c[1][0] * x[0]) / det,
+		)
+	} else {
+		(0.0, 0.0)
+	};
+	
+	// Degenerate or backwards handles: fall back to the Wu/Barsky heuristic.
+	let seg = len(sub(last, first));
+	let eps = 1e-6 * seg;
+	if alpha1 < eps || alpha2 < eps {
+		alpha1 = seg / 3.0;
+		alpha2 = seg / 3.0;
+	}
+	
+	[first, add(first, mul(t1, alpha1)), add(last, mul(t2, alpha2)), last]
+}
+
+fn max_error(points: &[Point], curve: &Cubic, u: &[f64]) -> (f64, usize) {
+	let mut worst = (0.0, points.len() / 2);
+	
+	for i in 1..points.len() - 1 {
+		let d = len(sub(eval(curve, u[i]), points[i]));
+		if d >= worst.0 {
+			worst = (d, i);
+		}
+	}
+	
+	worst
+}
+
+/// One round of Newton-Raphson to find better `t`s for each point.
+fn reparameterize(points: &[Point], u: &[f64], curve: &Cubic) -> Vec<f64> {
+	points.iter().zip(u).map(|(&p, &t)| {
+		let diff = sub(eval(curve, t), p);
+		let d1 = derivative(curve, t);
+		let d2 = second_derivative(curve, t);
+		
+		let denominator = dot(d1, d1) + dot(diff, d2);
+		if denominator.abs() < 1e-12 {
+			t
+		} else {
+			(t - dot(diff, d1) / denominator).clamp(0.0, 1.0)
+		}
+	}).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn worst_miss(points: &[Point], curves: &[Cubic]) -> f64 {
+		points.iter().map(|&p| {
+			curves.iter()
+				.flat_map(|c| (0..=200).map(move |i| eval(c, i as f64 / 200.0)))
+				.map(|q| len(sub(p, q)))
+				.fold(f64::INFINITY, f64::min)
+		}).fold(0.0, f64::max)
+	}
+	
+	#[test]
+	fn arc_fits_in_one_piece() {
+		let arc: Vec<Point> = (0..=12)
+			.map(|i| (i as f64 / 12.0) * std::f64::consts::FRAC_PI_2)
+			.map(|a| (10.0 * a.cos(), 10.0 * a.sin()))
+			.collect();
+		
+		let curves = fit_stroke(&arc, 0.1, 1.0);
+		assert_eq!(curves.len(), 1);
+		assert!(worst_miss(&arc, &curves) < 0.1);
+	}
+	
+	#[test]
+	fn corners_stay_sharp() {
+		let vee = vec![(0.0, 0.0), (2.0, 4.0), (4.0, 8.0), (6.0, 4.0), (8.0, 0.0)];
+		let curves = fit_stroke(&vee, 0.1, 0.5);
+		
+		assert_eq!(curves.len(), 2);
+		assert_eq!(curves[0][3], (4.0, 8.0));
+		assert_eq!(curves[1][0], (4.0, 8.0));
+	}
+	
+	#[test]
+	fn script_font_fits() {
+		let font = crate::test_font("scripts");
+		
+		for stroke in font.chars.iter().flat_map(HersheyChar::strokes) {
+			let curves = fit_stroke(&stroke, 0.5, 1.0);
+			assert!(worst_miss(&stroke, &curves) <= 0.5 + 1e-6);
+		}
+	}
+	
+	#[test]
+	fn svg_path_output() {
+		let chr = HersheyChar::new_from_str("12345  3JZPPTT").unwrap();
+		let glyph = CurveGlyph::new(&chr, 0.5, 1.0);
+		
+		assert_eq!(glyph.svg_path(), "M-2 -2 C-0.667 -0.667 0.667 0.667 2 2");
+	}
+}
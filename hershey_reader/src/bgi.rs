@@ -0,0 +1,334 @@
+//! Borland BGI stroke fonts (`.CHR`), the ones Turbo Pascal and Turbo C's
+//! graphics libraries drew text with.
+//!
+//! After a text blurb ending in `^Z` there's a little header, then at
+//! `header_size` a `+` and a table of contents: a stroke data offset and a
+//! width for each character. Strokes are pairs of bytes, each a 7-bit signed
+//! coordinate plus one bit of opcode. Coordinates have +y going up from the
+//! baseline, and x from the left edge of the character.
+
+use std::convert::TryFrom;
+
+use crate::{HersheyChar, HersheyError, HersheyFont};
+
+/// Where the `+` header starts, and how big everything before it is.
+const HEADER_SIZE: usize = 0x80;
+
+/// Code page 437, from 0x80 up, since that's what DOS fonts were drawn for.
+const CP437_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+fn from_cp437(b: u8) -> Option<char> {
+	match b {
+		0x20..=0x7E => Some(b as char),
+		0x80..=0xFF => CP437_HIGH.chars().nth(b as usize - 0x80),
+		_ => None,
+	}
+}
+
+fn to_cp437(c: char) -> Option<u8> {
+	match c {
+		' '..='~' => Some(c as u8),
+		_ => CP437_HIGH.chars().position(|h| h == c).map(|i| (i + 0x80) as u8),
+	}
+}
+
+/// What each pair of stroke bytes means.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+	End,
+	/// Supposedly for filling. Nothing uses it.
+	Scan,
+	Move(i8, i8),
+	Draw(i8, i8),
+}
+
+impl Op {
+	fn decode(a: u8, b: u8) -> Self {
+		// sign-extend from 7 bits
+		let (x, y) = (((a << 1) as i8) >> 1, ((b << 1) as i8) >> 1);
+		match (a & 0x80 != 0, b & 0x80 != 0) {
+			(false, false) => Op::End,
+			(false, true) => Op::Scan,
+			(true, false) => Op::Move(x, y),
+			(true, true) => Op::Draw(x, y),
+		}
+	}
+	
+	fn encode(self) -> [u8; 2] {
+		let (x, y, op) = match self {
+			Op::End => (0, 0, 0b00),
+			Op::Scan => (0, 0, 0b01),
+			Op::Move(x, y) => (x, y, 0b10),
+			Op::Draw(x, y) => (x, y, 0b11),
+		};
+		[(x as u8 & 0x7F) | (op & 0b10) << 6, (y as u8 & 0x7F) | (op & 0b01) << 7]
+	}
+}
+
+fn u16_at(data: &[u8], at: usize) -> Result<u16, HersheyError> {
+	match data.get(at..at + 2) {
+		Some(b) => Ok(u16::from_le_bytes([b[0], b[1]])),
+		None => Err(HersheyError::InvalidChr),
+	}
+}
+
+fn u8_at(data: &[u8], at: usize) -> Result<u8, HersheyError> {
+	data.get(at).copied().ok_or(HersheyError::InvalidChr)
+}
+
+/// Read a `.CHR` file. `baseline` is where the baseline should end up, in
+/// Hershey units; 9 matches the bundled fonts.
+///
+/// Characters come out centred on their origin like Hershey glyphs are,
+/// with a [`unicode`](HersheyFont::unicode) table going by code page 437.
+pub fn from_chr(data: &[u8], baseline: i8) -> Result<HersheyFont, HersheyError> {
+	if !data.starts_with(b"PK\x08\x08") { return Err(HersheyError::InvalidChr); }
+	
+	let blurb_end = data.iter().position(|&b| b == 0x1A).ok_or(HersheyError::InvalidChr)?;
+	let header_size = u16_at(data, blurb_end + 1)? as usize;
+	let name = data.get(blurb_end + 3..blurb_end + 7).ok_or(HersheyError::InvalidChr)?;
+	let name = String::from_utf8_lossy(name).trim_end_matches([' ', '\0']).to_string();
+	
+	let at = header_size;
+	if u8_at(data, at)? != b'+' { return Err(HersheyError::InvalidChr); }
+	let count = u16_at(data, at + 1)? as usize;
+	let first = u8_at(data, at + 4)?;
+	let strokes_at = at + u16_at(data, at + 5)? as usize;
+	
+	let offsets_at = at + 16;
+	let widths_at = offsets_at + 2 * count;
+	
+	let mut chars = Vec::with_capacity(count);
+	let mut unicode = Vec::with_capacity(count);
+	for i in 0..count {
+		let width = u8_at(data, widths_at + i)? as i32;
+		let left_hand = -(width / 2);
+		let mut pos = strokes_at + u16_at(data, offsets_at + 2 * i)? as usize;
+		
+		let to_hershey = |x: i8, y: i8| -> Result<(i8, i8), HersheyError> {
+			let x = i8::try_from(x as i32 + left_hand).map_err(|_| HersheyError::InvalidChr)?;
+			let y = i8::try_from(baseline as i32 - y as i32).map_err(|_| HersheyError::InvalidChr)?;
+			Ok((x, y))
+		};
+		
+		let mut vertex_data: Vec<Option<(i8, i8)>> = Vec::new();
+		let mut moved_to = None;
+		loop {
+			match Op::decode(u8_at(data, pos)?, u8_at(data, pos + 1)?) {
+				Op::End => break,
+				Op::Scan => {}
+				Op::Move(x, y) => moved_to = Some(to_hershey(x, y)?),
+				Op::Draw(x, y) => {
+					if let Some(from) = moved_to.take() {
+						if !vertex_data.is_empty() { vertex_data.push(None); }
+						vertex_data.push(Some(from));
+					}
+					let to = Some(to_hershey(x, y)?);
+					if vertex_data.last() != Some(&to) {
+						vertex_data.push(to);
+					}
+				}
+			}
+			pos += 2;
+		}
+		
+		chars.push(HersheyChar {
+			id: 12345, // like the bundled fonts
+			vertex_num: vertex_data.len(),
+			left_hand: left_hand as i8,
+			right_hand: i8::try_from(left_hand + width).map_err(|_| HersheyError::InvalidChr)?,
+			vertex_data,
+		});
+		unicode.push(u8::try_from(first as usize + i).ok().and_then(from_cp437));
+	}
+	
+	Ok(HersheyFont {
+		unicode: Some(unicode),
+		..HersheyFont::new(name, chars)
+	})
+}
+
+/// Write `font` as a `.CHR` file, covering everything in its
+/// [`char_map`](HersheyFont::char_map) that's in code page 437. `baseline`
+/// is where the font's baseline is, in Hershey units.
+///
+/// Fails with [`HersheyError::OutOfRange`] if a glyph doesn't fit in 7 bits
+/// either way from the baseline and left edge, or the file would be too big.
+///
+/// Like [`to_shp`](crate::shp::to_shp), there's no
+/// [`Transform`](crate::transform::Transform) option, since everything's in
+/// whole Hershey units. BGI scales text itself with `setusercharsize`.
+pub fn to_chr(font: &HersheyFont, baseline: i8) -> Result<Vec<u8>, HersheyError> {
+	let mut codes = font.char_map()
+		.into_iter()
+		.filter_map(|(c, i)| Some((to_cp437(c)?, i)))
+		.collect::<Vec<_>>();
+	codes.sort_by_key(|m| m.0);
+	codes.dedup_by_key(|m| m.0);
+	
+	let first = codes.first().map_or(b' ', |m| m.0);
+	let count = codes.last().map_or(0, |m| (m.0 - first) as usize + 1);
+	
+	let coord = |v: i32| -> Result<i8, HersheyError> {
+		if (-64..64).contains(&v) { Ok(v as i8) } else { Err(HersheyError::OutOfRange) }
+	};
+	
+	let mut offsets = Vec::with_capacity(count);
+	let mut widths = Vec::with_capacity(count);
+	let mut strokes = Vec::new();
+	let (mut top, mut bottom) = (0, 0);
+	
+	for code in 0..count {
+		let chr = codes.iter()
+			.find(|m| m.0 as usize == first as usize + code)
+			.map(|m| &font.chars[m.1]);
+		
+		offsets.push(u16::try_from(strokes.len()).map_err(|_| HersheyError::OutOfRange)?);
+		widths.push(chr.map_or(0, |c| c.advance().clamp(0, 255) as u8));
+		
+		let left_hand = chr.map_or(0, |c| c.left_hand as i32);
+		for stroke in chr.map(HersheyChar::strokes).unwrap_or_default() {
+			for (i, &(x, y)) in stroke.iter().enumerate() {
+				let x = coord(x as i32 - left_hand)?;
+				let y = coord(baseline as i32 - y as i32)?;
+				top = top.max(y);
+				bottom = bottom.min(y);
+				
+				let op = if i == 0 { Op::Move(x, y) } else { Op::Draw(x, y) };
+				strokes.extend(op.encode());
+				// a lone point still needs drawing
+				if stroke.len() == 1 {
+					strokes.extend(Op::Draw(x, y).encode());
+				}
+			}
+		}
+		strokes.extend(Op::End.encode());
+	}
+	
+	let cap_height = font.glyph_for('H')
+		.and_then(|chr| chr.vertex_data.iter().flatten().map(|v| v.1).min())
+		.map_or(top, |y| (baseline as i32 - y as i32).clamp(-64, 63) as i8);
+	
+	let mut name = font.name.chars()
+		.filter(char::is_ascii_alphanumeric)
+		.map(|c| c.to_ascii_uppercase() as u8)
+		.take(4)
+		.collect::<Vec<_>>();
+	name.resize(4, b' ');
+	
+	let mut out = Vec::new();
+	out.extend(b"PK\x08\x08BGI Stroked Font V1.1 - ");
+	out.extend(font.name.bytes().filter(|b| (b' '..=b'~').contains(b)).take(64));
+	out.extend(b"\r\n\x1A");
+	
+	out.extend((HEADER_SIZE as u16).to_le_bytes());
+	out.extend(&name);
+	let size_at = out.len();
+	out.extend([0, 0]); // file size, filled in at the end
+	out.extend([1, 1]); // font version
+	out.extend([1, 0]); // oldest BGI version that can read it
+	out.resize(HEADER_SIZE, 0);
+	
+	out.push(b'+');
+	out.extend((count as u16).to_le_bytes());
+	out.push(0);
+	out.push(first);
+	out.extend(((16 + 3 * count) as u16).to_le_bytes()); // where the strokes start
+	out.push(0); // scan flag
+	out.push(cap_height as u8);
+	out.push(0); // baseline
+	out.push(bottom as u8);
+	out.extend([0; 5]);
+	
+	for o in offsets {
+		out.extend(o.to_le_bytes());
+	}
+	out.extend(widths);
+	out.extend(strokes);
+	
+	let size = u16::try_from(out.len() - HEADER_SIZE).map_err(|_| HersheyError::OutOfRange)?;
+	out[size_at..size_at + 2].copy_from_slice(&size.to_le_bytes());
+	
+	Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	
+	#[test]
+	fn code_page() {
+		assert_eq!(CP437_HIGH.chars().count(), 128);
+		assert_eq!(from_cp437(0x82), Some('é'));
+		assert_eq!(to_cp437('é'), Some(0x82));
+		assert_eq!(to_cp437('A'), Some(b'A'));
+		assert_eq!(from_cp437(0x1B), None);
+	}
+	
+	#[test]
+	fn ops() {
+		for op in [Op::End, Op::Scan, Op::Move(-64, 63), Op::Draw(5, -3)] {
+			let [a, b] = op.encode();
+			assert_eq!(Op::decode(a, b), op);
+		}
+		assert_eq!(Op::Draw(-1, 2).encode(), [0xFF, 0x82]);
+	}
+	
+	#[test]
+	fn layout() -> Result<(), HersheyError> {
+		let data = to_chr(&test_font("futural"), 9)?;
+		
+		assert!(data.starts_with(b"PK\x08\x08BGI Stroked Font V1.1 - futural\r\n\x1A"));
+		assert_eq!(data[HEADER_SIZE], b'+');
+		assert_eq!(u16_at(&data, HEADER_SIZE + 1)?, 95);
+		assert_eq!(data[HEADER_SIZE + 4], b' ');
+		assert_eq!(data[HEADER_SIZE + 8], 21); // cap height
+		
+		// '-' is a line across the middle, 26 wide
+		let i = (b'-' - b' ') as usize;
+		let widths_at = HEADER_SIZE + 16 + 2 * 95;
+		assert_eq!(data[widths_at + i], 26);
+		
+		let at = HEADER_SIZE + 16 + 3 * 95 + u16_at(&data, HEADER_SIZE + 16 + 2 * i)? as usize;
+		assert_eq!(Op::decode(data[at], data[at + 1]), Op::Move(4, 9));
+		assert_eq!(Op::decode(data[at + 2], data[at + 3]), Op::Draw(22, 9));
+		assert_eq!(Op::decode(data[at + 4], data[at + 5]), Op::End);
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn round_trip() -> Result<(), HersheyError> {
+		let font = test_font("futural");
+		let back = from_chr(&to_chr(&font, 9)?, 9)?;
+		
+		assert_eq!(back.name, "FUTU");
+		assert_eq!(back.char_map(), font.char_map());
+		
+		for (a, b) in font.chars.iter().zip(&back.chars) {
+			let shift = b.left_hand - a.left_hand;
+			assert_eq!(a.advance(), b.advance());
+			assert_eq!(
+				a.vertex_data.iter().map(|v| v.map(|(x, y)| (x + shift, y))).collect::<Vec<_>>(),
+				b.vertex_data,
+			);
+		}
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn too_big() {
+		let font = HersheyFont::new("big", vec![HersheyChar::new_from_str("12345  2!Z~R").unwrap()]);
+		assert_eq!(to_chr(&font, 9), Err(HersheyError::OutOfRange));
+		assert_eq!(from_chr(b"not a font", 9), Err(HersheyError::InvalidChr));
+		
+		// a 255 wide character can't be centred in an i8
+		let mut data = to_chr(&test_font("futural"), 9).unwrap();
+		data[HEADER_SIZE + 16 + 2 * 95] = 254;
+		assert!(from_chr(&data, 9).is_ok());
+		data[HEADER_SIZE + 16 + 2 * 95] = 255;
+		assert_eq!(from_chr(&data, 9), Err(HersheyError::InvalidChr));
+	}
+}
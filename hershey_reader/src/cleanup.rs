@@ -0,0 +1,263 @@
+use crate::HersheyChar;
+
+type Vertex = (i8, i8);
+
+fn gcd(a: i32, b: i32) -> i32 {
+	if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+/// Direction and offset of an infinite line.
+type LineKey = (i32, i32, i32);
+
+/// Start and end along a line, and the vertices that are there.
+type Span = (i32, i32, Vertex, Vertex);
+
+/// Which infinite line a segment lies on, plus where along it the segment
+/// starts and ends. Segments with the same key can overlap.
+fn line_key(a: Vertex, b: Vertex) -> (LineKey, i32, i32) {
+	let (ax, ay) = (a.0 as i32, a.1 as i32);
+	let (bx, by) = (b.0 as i32, b.1 as i32);
+	
+	let g = gcd(bx - ax, by - ay);
+	let (mut dx, mut dy) = ((bx - ax) / g, (by - ay) / g);
+	if dx < 0 || (dx == 0 && dy < 0) {
+		dx = -dx;
+		dy = -dy;
+	}
+	
+	let offset = dx * ay - dy * ax;
+	let (ta, tb) = (dx * ax + dy * ay, dx * bx + dy * by);
+	((dx, dy, offset), ta, tb)
+}
+
+/// Every pen-down segment once, with overlapping collinear bits merged.
+fn unique_segments(chr: &HersheyChar) -> Vec<(Vertex, Vertex)> {
+	// in order of appearance
+	let mut lines: Vec<(LineKey, Vec<Span>)> = Vec::new();
+	
+	for run in chr.vertex_data.split(Option::is_none) {
+		for pair in run.windows(2) {
+			let (a, b) = match *pair {
+				[Some(a), Some(b)] if a != b => (a, b),
+				_ => continue,
+			};
+			
+			let (key, ta, tb) = line_key(a, b);
+			let span = if ta < tb { (ta, tb, a, b) } else { (tb, ta, b, a) };
+			
+			match lines.iter_mut().find(|(k, _)| *k == key) {
+				Some((_, spans)) => spans.push(span),
+				None => lines.push((key, vec![span])),
+			}
+		}
+	}
+	
+	let mut segments = Vec::new();
+	for (_, mut spans) in lines {
+		spans.sort_by_key(|s| s.0);
+		
+		let mut cur = spans[0];
+		for &next in &spans[1..] {
+			if next.0 <= cur.1 {
+				if next.1 > cur.1 {
+					cur.1 = next.1;
+					cur.3 = next.3;
+				}
+			} else {
+				segments.push((cur.2, cur.3));
+				cur = next;
+			}
+		}
+		segments.push((cur.2, cur.3));
+	}
+	
+	segments
+}
+
+/// Chain segments into as few polylines as it can manage.
+fn chain(segments: &[(Vertex, Vertex)]) -> Vec<Vec<Vertex>> {
+	let mut vertices: Vec<Vertex> = Vec::new();
+	let mut edges = Vec::new();
+	for &(a, b) in segments {
+		let mut index_of = |v: Vertex| vertices.iter()
+			.position(|&w| w == v)
+			.unwrap_or_else(|| { vertices.push(v); vertices.len() - 1 });
+		edges.push((index_of(a), index_of(b)));
+	}
+	
+	let mut used = vec![false; edges.len()];
+	let unused_at = |used: &[bool], v: usize| edges.iter()
+		.enumerate()
+		.filter(|&(e, &(a, b))| !used[e] && (a == v || b == v))
+		.map(|(e, _)| e)
+		.collect::<Vec<_>>();
+	
+	// Walk from odd vertices first, since that's where open paths have to end.
+	let mut paths = Vec::new();
+	loop {
+		let start = (0..vertices.len()).find(|&v| unused_at(&used, v).len() % 2 == 1)
+			.or_else(|| (0..vertices.len()).find(|&v| !unused_at(&used, v).is_empty()));
+		let mut cur = match start {
+			Some(v) => v,
+			None => break,
+		};
+		
+		let mut path = vec![vertices[cur]];
+		while let Some(&e) = unused_at(&used, cur).first() {
+			used[e] = true;
+			let (a, b) = edges[e];
+			cur = if a == cur { b } else { a };
+			path.push(vertices[cur]);
+		}
+		paths.push(path);
+	}
+	
+	join(paths)
+}
+
+/// Splice loops into whatever other path runs through them,
+/// and glue together paths whose ends meet.
+fn join(mut paths: Vec<Vec<Vertex>>) -> Vec<Vec<Vertex>> {
+	let mut changed = true;
+	while changed {
+		changed = false;
+		
+		'outer: for i in 0..paths.len() {
+			for j in 0..paths.len() {
+				if i == j { continue; }
+				let (p, q) = (&paths[i], &paths[j]);
+				
+				if p.first() == p.last() {
+					if let Some(at) = q.iter().position(|v| p.contains(v)) {
+						let from = p.iter().position(|&v| v == q[at]).unwrap();
+						let mut rotated = p[from..].to_vec();
+						rotated.extend_from_slice(&p[1..=from]);
+						
+						paths[j].splice(at..=at, rotated);
+						paths.remove(i);
+						changed = true;
+						break 'outer;
+					}
+				}
+				
+				let joined = if p.last() == q.first() {
+					p.iter().chain(&q[1..]).copied().collect()
+				} else if p.last() == q.last() {
+					p.iter().chain(q.iter().rev().skip(1)).copied().collect()
+				} else if p.first() == q.first() {
+					p.iter().rev().chain(&q[1..]).copied().collect()
+				} else {
+					continue;
+				};
+				
+				paths[i] = joined;
+				paths.remove(j);
+				changed = true;
+				break 'outer;
+			}
+		}
+	}
+	
+	paths
+}
+
+/// Tidy up a character for plotting: segments drawn twice (or overlapping
+/// along the same line) are only drawn once, strokes that meet end to end
+/// become one stroke, and strokes that don't go anywhere are dropped.
+///
+/// It still looks the same, it just lifts the pen less. Sometimes a glyph
+/// goes back over a segment on purpose to avoid a pen-up; if dropping the
+/// repeat would mean *more* pen-ups, the strokes are only joined instead.
+pub fn merge_strokes(chr: &HersheyChar) -> HersheyChar {
+	let deduped = chain(&unique_segments(chr));
+	
+	let joined = join(chr.vertex_data
+		.split(Option::is_none)
+		.map(|run| run.iter().flatten().copied().collect::<Vec<_>>())
+		.filter(|run| run.windows(2).any(|pair| pair[0] != pair[1]))
+		.collect());
+	
+	let strokes = if deduped.len() <= joined.len() { deduped } else { joined };
+	
+	let mut vertex_data = Vec::new();
+	for stroke in strokes {
+		if !vertex_data.is_empty() {
+			vertex_data.push(None);
+		}
+		vertex_data.extend(stroke.into_iter().map(Some));
+	}
+	
+	HersheyChar {
+		vertex_num: vertex_data.len(),
+		vertex_data,
+		..chr.clone()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::HersheyError;
+	
+	#[test]
+	fn joins_and_dedupes() -> Result<(), HersheyError> {
+		// Two halves of a line that meet in the middle, the same line again
+		// backwards, a line that overlaps half of it, and a dot.
+		const CHR: &str = "12345 14JZPPRP RRPTP RTPPP RQPSP RUU";
+		let chr = HersheyChar::new_from_str(CHR)?;
+		assert_eq!(chr.strokes().len(), 5);
+		
+		let merged = merge_strokes(&chr);
+		assert_eq!(merged.vertex_data, vec![Some((-2, -2)), Some((2, -2))]);
+		assert_eq!(merged.vertex_num, 2);
+		assert_eq!(merged.left_hand, chr.left_hand);
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn splices_loops() -> Result<(), HersheyError> {
+		// A line, and a triangle hanging off its far end
+		const CHR: &str = "12345  8JZNRRR RRRTPTTRR";
+		let merged = merge_strokes(&HersheyChar::new_from_str(CHR)?);
+		
+		assert_eq!(merged.strokes().len(), 1);
+		assert_eq!(merged.vertex_data.len(), 5);
+		
+		Ok(())
+	}
+	
+	fn on_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> bool {
+		let cross = (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0);
+		let within = p.0 >= a.0.min(b.0) - 1e-9 && p.0 <= a.0.max(b.0) + 1e-9
+			&& p.1 >= a.1.min(b.1) - 1e-9 && p.1 <= a.1.max(b.1) + 1e-9;
+		cross.abs() < 1e-9 && within
+	}
+	
+	fn covers(strokes: &[crate::Stroke], other: &[crate::Stroke]) -> bool {
+		other.iter().flat_map(|s| s.windows(2)).all(|pair| {
+			let mid = ((pair[0].0 + pair[1].0) / 2.0, (pair[0].1 + pair[1].1) / 2.0);
+			strokes.iter().flat_map(|s| s.windows(2)).any(|q| on_segment(mid, q[0], q[1]))
+		})
+	}
+	
+	// Nothing gets lost or added, and it never gets worse.
+	#[test]
+	fn every_font_still_looks_the_same() {
+		use std::fs::{read_dir, read_to_string};
+		
+		for entry in read_dir("../fonts/").unwrap() {
+			let path = entry.unwrap().path();
+			if path.extension().and_then(|e| e.to_str()) != Some("jhf") { continue; }
+			
+			let font = crate::HersheyFont::new_from_str("", &read_to_string(&path).unwrap()).unwrap();
+			for chr in &font.chars {
+				let (before, after) = (chr.strokes(), merge_strokes(chr).strokes());
+				
+				assert!(after.len() <= before.len(), "{:?}", path);
+				assert!(covers(&before, &after), "{:?}", path);
+				assert!(covers(&after, &before), "{:?}", path);
+			}
+		}
+	}
+}
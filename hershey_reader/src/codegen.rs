@@ -0,0 +1,260 @@
+//! Rust source for a font, as plain `const` tables and a couple of
+//! functions, so it can be checked in and built without this crate (or
+//! `std`) around. Good for firmware.
+
+use std::fmt::Write;
+
+use crate::HersheyFont;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RustOptions<'a> {
+	/// Only keep these characters. `None` keeps everything in the font's
+	/// [`char_map`](HersheyFont::char_map); anything the font doesn't have
+	/// gets skipped.
+	pub subset: Option<&'a str>,
+}
+
+/// Write out `items` as the inside of an array literal, a few to a line.
+fn array(out: &mut String, items: &[String], per_line: usize) {
+	for line in items.chunks(per_line.max(1)) {
+		writeln!(out, "\t{},", line.join(", ")).unwrap();
+	}
+}
+
+/// A module's worth of tables for `font`, doc comment and all.
+fn module(font: &HersheyFont, opts: &RustOptions) -> String {
+	let mut map = font.char_map();
+	if let Some(subset) = opts.subset {
+		map.retain(|(c, _)| subset.contains(*c));
+	}
+	// `char_map` is in glyph order, so this keeps whichever one `glyph_for` would find
+	map.sort_by_key(|&(c, _)| c);
+	map.dedup_by_key(|&mut (c, _)| c);
+	
+	let mut glyphs = vec![0];
+	let mut strokes = Vec::new();
+	let mut points = Vec::new();
+	for &(_, i) in &map {
+		for run in font.chars[i].vertex_data.split(Option::is_none).filter(|run| !run.is_empty()) {
+			points.extend(run.iter().flatten());
+			strokes.push(points.len());
+		}
+		glyphs.push(strokes.len());
+	}
+	
+	let (index, index_size) = if points.len() <= u16::MAX as usize { ("u16", 2) } else { ("u32", 4) };
+	let bytes = map.len() * (4 + 1 + 2) + (glyphs.len() + strokes.len()) * index_size + points.len() * 2;
+	
+	let mut out = String::new();
+	writeln!(out, "//! `{}`, generated by hershey_reader. Don't edit this by hand.", font.name).unwrap();
+	writeln!(out, "//!").unwrap();
+	writeln!(out, "//! Points are `(x, y)` in Hershey units, with +y down and the origin in").unwrap();
+	writeln!(out, "//! the middle of the glyph. {} glyphs, {} bytes of tables.", map.len(), bytes).unwrap();
+	writeln!(out).unwrap();
+	
+	writeln!(out, "/// Every character there's a glyph for, sorted. Glyphs are in this order too.").unwrap();
+	writeln!(out, "pub const CHARS: [char; {}] = [", map.len()).unwrap();
+	array(&mut out, &map.iter().map(|(c, _)| format!("{:?}", c)).collect::<Vec<_>>(), 12);
+	writeln!(out, "];").unwrap();
+	writeln!(out).unwrap();
+	
+	writeln!(out, "/// Where each glyph's left edge is, from its origin.").unwrap();
+	writeln!(out, "pub const LEFT_HAND: [i8; {}] = [", map.len()).unwrap();
+	array(&mut out, &map.iter().map(|&(_, i)| font.chars[i].left_hand.to_string()).collect::<Vec<_>>(), 16);
+	writeln!(out, "];").unwrap();
+	writeln!(out).unwrap();
+	
+	writeln!(out, "/// How far along the next glyph's left edge is from this one's.").unwrap();
+	writeln!(out, "pub const ADVANCES: [i16; {}] = [", map.len()).unwrap();
+	array(&mut out, &map.iter().map(|&(_, i)| font.chars[i].advance().to_string()).collect::<Vec<_>>(), 16);
+	writeln!(out, "];").unwrap();
+	writeln!(out).unwrap();
+	
+	writeln!(out, "/// Glyph `g`'s strokes are `STROKES[GLYPHS[g]..GLYPHS[g + 1]]`.").unwrap();
+	writeln!(out, "pub const GLYPHS: [{}; {}] = [", index, glyphs.len()).unwrap();
+	array(&mut out, &glyphs.iter().map(usize::to_string).collect::<Vec<_>>(), 16);
+	writeln!(out, "];").unwrap();
+	writeln!(out).unwrap();
+	
+	writeln!(out, "/// Where each stroke ends in [`POINTS`]. Each one starts where the one before it ended.").unwrap();
+	writeln!(out, "pub const STROKES: [{}; {}] = [", index, strokes.len()).unwrap();
+	array(&mut out, &strokes.iter().map(usize::to_string).collect::<Vec<_>>(), 16);
+	writeln!(out, "];").unwrap();
+	writeln!(out).unwrap();
+	
+	writeln!(out, "pub const POINTS: [(i8, i8); {}] = [", points.len()).unwrap();
+	array(&mut out, &points.iter().map(|(x, y)| format!("({}, {})", x, y)).collect::<Vec<_>>(), 8);
+	writeln!(out, "];").unwrap();
+	writeln!(out).unwrap();
+	
+	writeln!(out, "/// Which glyph `c` is, if there is one.").unwrap();
+	writeln!(out, "pub fn glyph(c: char) -> Option<usize> {{").unwrap();
+	writeln!(out, "\tCHARS.binary_search(&c).ok()").unwrap();
+	writeln!(out, "}}").unwrap();
+	writeln!(out).unwrap();
+	
+	writeln!(out, "/// Each of a glyph's strokes, as a run of connected points.").unwrap();
+	writeln!(out, "pub fn strokes(glyph: usize) -> impl Iterator<Item = &'static [(i8, i8)]> {{").unwrap();
+	writeln!(out, "\t(GLYPHS[glyph] as usize..GLYPHS[glyph + 1] as usize).map(|s| {{").unwrap();
+	writeln!(out, "\t\tlet start = if s == 0 {{ 0 }} else {{ STROKES[s - 1] as usize }};").unwrap();
+	writeln!(out, "\t\t&POINTS[start..STROKES[s] as usize]").unwrap();
+	writeln!(out, "\t}})").unwrap();
+	writeln!(out, "}}").unwrap();
+	
+	out
+}
+
+/// A whole `.rs` file for `font`, meant to be its own module.
+pub fn to_rust(font: &HersheyFont, opts: &RustOptions) -> String {
+	module(font, opts)
+}
+
+/// Everything that can't be a module name, lowercase ones only since
+/// [`ident`] lowercases everything. Includes the reserved ones.
+const KEYWORDS: &[&str] = &[
+	"abstract", "as", "async", "await", "become", "box", "break", "const",
+	"continue", "crate", "do", "dyn", "else", "enum", "extern", "false",
+	"final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+	"match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+	"self", "static", "struct", "super", "trait", "true", "try", "type",
+	"typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Turn a font name into something that works as a module name.
+fn ident(name: &str) -> String {
+	let name = name.strip_suffix(".jhf").unwrap_or(name);
+	let mut ident = name.chars()
+		.map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+		.collect::<String>();
+	
+	// nothing in there to go on, and `_` on its own isn't a name
+	if ident.chars().all(|c| c == '_') {
+		return "font".to_string();
+	}
+	if ident.starts_with(|c: char| c.is_ascii_digit()) {
+		ident.insert(0, '_');
+	}
+	// `r#` doesn't work for `crate`, `self` or `super`, so this is simpler
+	if KEYWORDS.contains(&ident.as_str()) {
+		ident.push('_');
+	}
+	ident
+}
+
+/// A whole `.rs` file with a module for each of `fonts`, named after them
+/// (with a number on the end if two names come out the same).
+pub fn to_rust_fonts(fonts: &[HersheyFont], opts: &RustOptions) -> String {
+	let mut out = String::new();
+	writeln!(out, "//! Generated by hershey_reader. Don't edit this by hand.").unwrap();
+	
+	let mut names = Vec::new();
+	for font in fonts {
+		// `a-b` and `a_b` both come out as `a_b`, so number the extras
+		let base = ident(&font.name);
+		let mut name = base.clone();
+		for n in 2.. {
+			if !names.contains(&name) { break; }
+			name = format!("{}_{}", base, n);
+		}
+		
+		writeln!(out).unwrap();
+		writeln!(out, "pub mod {} {{", name).unwrap();
+		names.push(name);
+		for line in module(font, opts).lines() {
+			if line.is_empty() { writeln!(out).unwrap(); }
+			else { writeln!(out, "\t{}", line).unwrap(); }
+		}
+		writeln!(out, "}}").unwrap();
+	}
+	
+	out
+}
+
+// built with the tests, so there's proof the generated code compiles
+#[cfg(test)]
+#[path = "../testdata/futural_subset.rs"]
+mod futural_subset;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	
+	const SUBSET: RustOptions = RustOptions { subset: Some("Hi! é") };
+	
+	#[test]
+	fn matches_snapshot() {
+		let rs = to_rust(&test_font("futural"), &SUBSET);
+		let snapshot = std::fs::read_to_string("testdata/futural_subset.rs").unwrap();
+		
+		// cargo run --example jhf_rust -- ../fonts/futural.jhf "Hi! é" > testdata/futural_subset.rs
+		assert_eq!(rs, snapshot, "the generator changed, check the difference and make a new snapshot");
+	}
+	
+	#[test]
+	fn generated_tables() {
+		use super::futural_subset::*;
+		
+		let font = test_font("futural");
+		assert_eq!(CHARS, [' ', '!', 'H', 'i']);
+		assert_eq!(glyph('é'), None);
+		
+		for c in CHARS {
+			let g = glyph(c).unwrap();
+			let chr = font.glyph_for(c).unwrap();
+			
+			assert_eq!(LEFT_HAND[g], chr.left_hand);
+			assert_eq!(ADVANCES[g] as i32, chr.advance());
+			
+			let strokes = strokes(g)
+				.map(|s| s.iter().map(|&(x, y)| (x as f64, y as f64)).collect::<Vec<_>>())
+				.collect::<Vec<_>>();
+			assert_eq!(strokes, chr.strokes(), "{:?}", c);
+		}
+	}
+	
+	#[test]
+	fn every_font_together() {
+		use std::fs::{read_dir, read_to_string};
+		use std::ffi::OsStr;
+		
+		let mut fonts = Vec::new();
+		for entry in read_dir("../fonts/").unwrap() {
+			let path = entry.unwrap().path();
+			
+			if path.is_file()
+			&& path.extension().and_then(OsStr::to_str) == Some("jhf") {
+				let name = path.file_name().unwrap().to_string_lossy();
+				fonts.push(HersheyFont::new_from_str(name, &read_to_string(&path).unwrap()).unwrap());
+			}
+		}
+		
+		let rs = to_rust_fonts(&fonts, &RustOptions::default());
+		assert!(rs.contains("\npub mod futural {\n\t//! `futural.jhf`, generated by hershey_reader."));
+		assert!(rs.contains("\n\tpub const CHARS: [char; 95] = [\n"));
+		assert_eq!(rs.matches("\npub mod ").count(), fonts.len());
+		
+		assert_eq!(ident("cyrilc_1.jhf"), "cyrilc_1");
+		assert_eq!(ident("3D Font"), "_3d_font");
+	}
+	
+	#[test]
+	fn awkward_names() {
+		assert_eq!(ident(""), "font");
+		assert_eq!(ident("_"), "font");
+		assert_eq!(ident("?!.jhf"), "font");
+		assert_eq!(ident("type"), "type_");
+		assert_eq!(ident("FN.jhf"), "fn_");
+		assert_eq!(ident("mod"), "mod_");
+		assert_eq!(ident("self"), "self_");
+		assert_eq!(ident("types"), "types");
+		
+		let font = |name: &str| HersheyFont::new(name, test_font("futural").chars);
+		let fonts = [font("a-b"), font("a_b"), font("a b"), font("a_b_2"), font("")];
+		let rs = to_rust_fonts(&fonts, &RustOptions { subset: Some("a") });
+		let mods = rs.lines()
+			.filter_map(|l| l.strip_prefix("pub mod "))
+			.collect::<Vec<_>>();
+		assert_eq!(mods, ["a_b {", "a_b_2 {", "a_b_3 {", "a_b_2_2 {", "font {"]);
+	}
+}
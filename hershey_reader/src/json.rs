@@ -0,0 +1,248 @@
+//! Fonts as JSON, for things that would rather not parse .jhf. Needs the
+//! `serde` feature, which also gives [`HersheyChar`] and [`HersheyFont`]
+//! `Serialize`/`Deserialize` impls that use this same layout.
+//!
+//! A font looks like this:
+//!
+//! ```json
+//! {
+//!   "format": "hershey",
+//!   "version": 1,
+//!   "name": "futural",
+//!   "ascii_order": true,
+//!   "glyphs": [
+//!     { "char": " ", "id": 12345, "left": -8, "right": 8, "advance": 16, "strokes": [] },
+//!     { "char": "!", "id": 12345, "left": -5, "right": 5, "advance": 10,
+//!       "strokes": [[[0, -12], [0, 2]], [[0, 7], [-1, 8], [0, 9], [1, 8], [0, 7]]] }
+//!   ]
+//! }
+//! ```
+//!
+//! - `format` is always `"hershey"`, and `version` is `1`. Fields might get
+//!   added without a new version, but nothing will change meaning or go away.
+//! - `ascii_order` is `true` when the font has no [`unicode`](HersheyFont::unicode)
+//!   table. `char` is filled in either way, and is `null` for glyphs that
+//!   aren't any character.
+//! - `left` and `right` are the left and right hand. `advance` is just
+//!   `right - left`, for convenience; it's ignored when reading.
+//! - `strokes` are pen-down runs of `[x, y]` points, in Hershey units with
+//!   +y down and the origin in the middle of the glyph. A one-point stroke
+//!   is a dot. Empty strokes only show up when the .jhf had two pen-ups in
+//!   a row, and are kept so it comes back out the same.
+
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{HersheyChar, HersheyError, HersheyFont};
+
+const FORMAT: &str = "hershey";
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct JsonChar {
+	#[serde(default)]
+	id: usize,
+	left: i8,
+	right: i8,
+	#[serde(default)]
+	advance: i32,
+	strokes: Vec<Vec<(i8, i8)>>,
+}
+
+impl From<HersheyChar> for JsonChar {
+	fn from(chr: HersheyChar) -> Self {
+		// not `strokes()`, since that drops empty runs
+		let strokes = if chr.vertex_data.is_empty() { Vec::new() } else {
+			chr.vertex_data
+				.split(Option::is_none)
+				.map(|run| run.iter().flatten().copied().collect())
+				.collect()
+		};
+		
+		JsonChar {
+			id: chr.id,
+			left: chr.left_hand,
+			right: chr.right_hand,
+			advance: chr.advance(),
+			strokes,
+		}
+	}
+}
+
+impl From<JsonChar> for HersheyChar {
+	fn from(json: JsonChar) -> Self {
+		let mut vertex_data = Vec::new();
+		for (i, stroke) in json.strokes.into_iter().enumerate() {
+			if i > 0 { vertex_data.push(None); }
+			vertex_data.extend(stroke.into_iter().map(Some));
+		}
+		
+		HersheyChar {
+			id: json.id,
+			vertex_num: vertex_data.len(),
+			left_hand: json.left,
+			right_hand: json.right,
+			vertex_data,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonGlyph {
+	#[serde(default)]
+	char: Option<char>,
+	#[serde(flatten)]
+	chr: HersheyChar,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct JsonFont {
+	format: String,
+	version: u32,
+	name: String,
+	#[serde(default)]
+	ascii_order: bool,
+	glyphs: Vec<JsonGlyph>,
+}
+
+impl From<HersheyFont> for JsonFont {
+	fn from(font: HersheyFont) -> Self {
+		let mut chars = vec![None; font.chars.len()];
+		for (c, i) in font.char_map() {
+			chars[i] = Some(c);
+		}
+		
+		JsonFont {
+			format: FORMAT.to_owned(),
+			version: VERSION,
+			name: font.name,
+			ascii_order: font.unicode.is_none(),
+			glyphs: font.chars.into_iter()
+				.zip(chars)
+				.map(|(chr, char)| JsonGlyph { char, chr })
+				.collect(),
+		}
+	}
+}
+
+impl TryFrom<JsonFont> for HersheyFont {
+	type Error = String;
+	
+	fn try_from(json: JsonFont) -> Result<Self, Self::Error> {
+		if json.format != FORMAT {
+			return Err(format!("format is {:?}, not {:?}", json.format, FORMAT));
+		}
+		if json.version != VERSION {
+			return Err(format!("don't know version {}", json.version));
+		}
+		
+		let (chars, unicode): (Vec<_>, Vec<_>) = json.glyphs.into_iter()
+			.map(|g| (g.chr, g.char))
+			.unzip();
+		
+		Ok(HersheyFont {
+			unicode: if json.ascii_order { None } else { Some(unicode) },
+			..HersheyFont::new(json.name, chars)
+		})
+	}
+}
+
+/// Write `font` as JSON, one glyph per line so it diffs nicely.
+pub fn to_json(font: &HersheyFont) -> String {
+	let json = JsonFont::from(font.clone());
+	
+	let glyphs = json.glyphs.iter()
+		.map(|g| serde_json::to_string(g).unwrap())
+		.collect::<Vec<_>>();
+	
+	format!(
+		"{{\"format\":{},\"version\":{},\"name\":{},\"ascii_order\":{},\"glyphs\":[\n{}\n]}}\n",
+		serde_json::to_string(&json.format).unwrap(),
+		json.version,
+		serde_json::to_string(&json.name).unwrap(),
+		json.ascii_order,
+		glyphs.join(",\n"),
+	)
+}
+
+/// Read a font written by [`to_json`], or anything else that fits the layout
+/// up top. Fails with [`HersheyError::InvalidJson`] if it doesn't.
+pub fn from_json(s: &str) -> Result<HersheyFont, HersheyError> {
+	serde_json::from_str(s).map_err(|e| HersheyError::InvalidJson(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	
+	#[test]
+	fn glyph_layout() -> Result<(), HersheyError> {
+		let json = to_json(&test_font("futural"));
+		let lines = json.lines().collect::<Vec<_>>();
+		
+		assert_eq!(lines[0], r#"{"format":"hershey","version":1,"name":"futural","ascii_order":true,"glyphs":["#);
+		assert_eq!(lines[1], r#"{"char":" ","id":12345,"left":-8,"right":8,"advance":16,"strokes":[]},"#);
+		assert_eq!(lines[2], r#"{"char":"!","id":12345,"left":-5,"right":5,"advance":10,"strokes":[[[0,-12],[0,2]],[[0,7],[-1,8],[0,9],[1,8],[0,7]]]},"#);
+		assert_eq!(lines.last(), Some(&"]}"));
+		
+		// same thing as the derived impls, just with different whitespace
+		let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+		assert_eq!(value, serde_json::to_value(test_font("futural")).unwrap());
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn round_trip_every_font() -> Result<(), HersheyError> {
+		use std::fs::{read_dir, read_to_string};
+		use std::ffi::OsStr;
+		
+		for entry in read_dir("../fonts/").unwrap() {
+			let path = entry.unwrap().path();
+			
+			if path.is_file()
+			&& path.extension().and_then(OsStr::to_str) == Some("jhf") {
+				let jhf = read_to_string(&path).unwrap();
+				let font = HersheyFont::new_from_str("font", &jhf)?;
+				let back = from_json(&to_json(&font))?;
+				
+				assert_eq!(back, font, "{:?}", path);
+				assert_eq!(back.to_jhf()?, jhf, "{:?}", path);
+			}
+		}
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn keeps_odd_bits() -> Result<(), HersheyError> {
+		// a doubled-up pen-up, and a glyph that isn't any character
+		let chr = HersheyChar::new_from_str("    7  7JZPFPF R RTFTF")?;
+		let font = HersheyFont {
+			unicode: Some(vec![Some('α'), None]),
+			..HersheyFont::new("odd", vec![chr.clone(), chr])
+		};
+		
+		let json = to_json(&font);
+		assert!(json.contains(r#""char":"α","id":7,"left":-8,"right":8,"advance":16,"strokes":[[[-2,-12],[-2,-12]],[],[[2,-12],[2,-12]]]}"#));
+		assert!(json.contains(r#""char":null"#));
+		assert_eq!(from_json(&json)?, font);
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn bad_json() {
+		assert!(matches!(from_json("{"), Err(HersheyError::InvalidJson(_))));
+		assert!(matches!(
+			from_json(r#"{"format":"hershey","version":2,"name":"","glyphs":[]}"#),
+			Err(HersheyError::InvalidJson(e)) if e.contains("version 2")
+		));
+		
+		// advance and id can be left out
+		let font = from_json(r#"{"format":"hershey","version":1,"name":"x","glyphs":[{"char":"x","left":-3,"right":3,"strokes":[[[0,0]]]}]}"#).unwrap();
+		assert_eq!(font.glyph_for('x').unwrap().vertex_data, vec![Some((0, 0))]);
+	}
+}
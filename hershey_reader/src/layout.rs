@@ -0,0 +1,216 @@
+use core::f64::consts::FRAC_PI_2;
+
+use crate::{HersheyChar, HersheyFont, Stroke};
+use crate::transform::Transform;
+
+/// Distance between baselines, in font units. Not in the font data anywhere,
+/// it's just what looked right in the viewer.
+pub const LINE_HEIGHT: f64 = 32.0;
+
+/// How far below the glyph origin the bundled fonts' baseline is. The
+/// origin itself is the math axis, where the minus sign and fraction bars go.
+pub const BASELINE: f64 = 9.0;
+
+/// A character that's been given a spot on the page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacedChar<'a> {
+	pub chr: &'a HersheyChar,
+	
+	/// Takes the character's own coordinates to wherever it ended up.
+	pub transform: Transform,
+	
+	/// Which line of the text this is on, starting from 0.
+	pub line: usize,
+	
+	/// Which `char` of the text this came from, starting from 0.
+	pub index: usize,
+}
+
+impl PlacedChar<'_> {
+	pub fn strokes(&self) -> Vec<Stroke> {
+		self.chr.transformed(&self.transform)
+	}
+}
+
+/// Somewhere to get glyphs from: a [`HersheyFont`], or a
+/// [`FontStack`](crate::stack::FontStack) of them.
+pub trait Glyphs {
+	/// The glyph for `c`, and how much it needs scaling by to match
+	/// everything else.
+	fn sized_glyph(&self, c: char) -> Option<(&HersheyChar, f64)>;
+}
+
+impl Glyphs for HersheyFont {
+	fn sized_glyph(&self, c: char) -> Option<(&HersheyChar, f64)> {
+		Some((self.glyph_for(c)?, 1.0))
+	}
+}
+
+/// Lay out `text` left to right, one line per `\n`, then put the whole thing
+/// through `t`. Characters the font doesn't have are skipped.
+pub fn layout_str<'a, F: Glyphs + ?Sized>(font: &'a F, text: &str, t: &Transform) -> Vec<PlacedChar<'a>> {
+	let mut placed = Vec::new();
+	let mut line = 0;
+	let mut pen_x = 0.0;
+	
+	for (index, ch) in text.chars().enumerate() {
+		if ch == '\n' {
+			line += 1;
+			pen_x = 0.0;
+			continue;
+		}
+		
+		if let Some((chr, scale)) = font.sized_glyph(ch) {
+			let origin = Transform::translate(-chr.left_hand as f64, 0.0)
+				.then(&Transform::uniform_scale(scale))
+				.then(&Transform::translate(pen_x, line as f64 * LINE_HEIGHT));
+			
+			placed.push(PlacedChar {
+				chr,
+				transform: origin.then(t),
+				line, index,
+			});
+			pen_x += chr.advance() as f64 * scale;
+		}
+	}
+	
+	placed
+}
+
+/// How far the pen moves for `line`, in font units.
+/// Anything after a `\n` counts too, so split lines first.
+pub fn line_width<F: Glyphs + ?Sized>(font: &F, line: &str) -> f64 {
+	line.chars()
+		.filter_map(|ch| font.sized_glyph(ch))
+		.map(|(chr, scale)| chr.advance() as f64 * scale)
+		.sum()
+}
+
+/// How a character sits in a column of vertical text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerticalForm {
+	/// Same way up as in horizontal text. Most things, kana and kanji included.
+	Upright,
+	
+	/// A quarter turn clockwise, so it runs along the column. Dashes,
+	/// brackets and the long vowel mark.
+	Rotated,
+	
+	/// Moved from the bottom left of its cell to the top right, like 、 and 。.
+	Corner,
+}
+
+impl VerticalForm {
+	pub fn of(ch: char) -> Self {
+		match ch {
+			'ー' | '－' | '—' | '―' | '–' | '-' | '‐' | '~' | '〜' | '～' | '…' | '‥'
+			| '(' | ')' | '[' | ']' | '{' | '}' | '<' | '>'
+			| '（' | '）' | '［' | '］' | '｛' | '｝' | '〈' | '〉' | '《' | '》'
+			| '「' | '」' | '『' | '』' | '【' | '】' | '〔' | '〕' => VerticalForm::Rotated,
+			'、' | '。' | '，' | '．' => VerticalForm::Corner,
+			_ => VerticalForm::Upright,
+		}
+	}
+}
+
+/// Lay out `text` top to bottom, the way Japanese is written vertically
+/// (tategaki). Each `\n` starts a new column to the left of the last one,
+/// [`LINE_HEIGHT`] apart. The first column is centred on x = 0, and
+/// starts at y = 0.
+/// 
+/// Glyphs don't have vertical metrics, so each one gets a square cell as
+/// tall as it is wide, and is centred in it. Punctuation gets turned or
+/// moved as per [`VerticalForm`].
+pub fn layout_str_vertical<'a, F: Glyphs + ?Sized>(font: &'a F, text: &str, t: &Transform) -> Vec<PlacedChar<'a>> {
+	let mut placed = Vec::new();
+	let mut line = 0;
+	let mut pen_y = 0.0;
+	
+	for (index, ch) in text.chars().enumerate() {
+		if ch == '\n' {
+			line += 1;
+			pen_y = 0.0;
+			continue;
+		}
+		
+		if let Some((chr, scale)) = font.sized_glyph(ch) {
+			let advance = chr.advance() as f64 * scale;
+			let middle = (chr.left_hand as f64 + chr.right_hand as f64) / 2.0;
+			
+			let centre = Transform::translate(-middle, 0.0).then(&Transform::uniform_scale(scale));
+			let form = match VerticalForm::of(ch) {
+				VerticalForm::Upright => centre,
+				VerticalForm::Rotated => centre.then(&Transform::rotate(FRAC_PI_2)),
+				VerticalForm::Corner => centre.then(&Transform::translate(advance * 0.6, -advance * 0.6)),
+			};
+			let origin = Transform::translate(
+				-(line as f64) * LINE_HEIGHT,
+				pen_y + advance / 2.0,
+			);
+			
+			placed.push(PlacedChar {
+				chr,
+				transform: form.then(&origin).then(t),
+				line, index,
+			});
+			pen_y += advance;
+		}
+	}
+	
+	placed
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn font() -> HersheyFont {
+		// space, !, and a fake wide "
+		HersheyFont::new_from_str("test", "12345  1JZ\n12345  9MWRFRT RRYQZR[SZRY\n12345  2F^").unwrap()
+	}
+	
+	#[test]
+	fn advances_and_lines() {
+		let font = font();
+		let placed = layout_str(&font, "!\"\n !", &Transform::IDENTITY);
+		
+		assert_eq!(placed.len(), 4);
+		// "!" has left_hand -5, so its origin lands at x = 5
+		assert_eq!(placed[0].transform.apply((0.0, 0.0)), (5.0, 0.0));
+		// then the " starts at 10 with left_hand -12
+		assert_eq!(placed[1].transform.apply((0.0, 0.0)), (22.0, 0.0));
+		
+		assert_eq!(placed[3].line, 1);
+		assert_eq!(placed[3].index, 4);
+		assert_eq!(placed[3].transform.apply((0.0, 0.0)), (16.0 + 5.0, LINE_HEIGHT));
+		
+		assert_eq!(line_width(&font, "!\" !"), 10.0 + 24.0 + 16.0 + 10.0);
+	}
+	
+	#[test]
+	fn vertical_columns() {
+		let mut font = font();
+		font.unicode = Some(vec![Some('日'), Some('。'), Some('ー')]);
+		let placed = layout_str_vertical(&font, "日。\nー", &Transform::IDENTITY);
+		
+		assert_eq!(placed.len(), 3);
+		// the space is 16 wide, so its cell goes from 0 to 16
+		assert_eq!(placed[0].transform.apply((0.0, 0.0)), (0.0, 8.0));
+		// "!" is 10 wide, in the cell from 16 to 26, then moved up and right by 6
+		assert_eq!(placed[1].transform.apply((0.0, 0.0)), (6.0, 15.0));
+		
+		// next column over, turned so the top of the glyph points right
+		assert_eq!(placed[2].line, 1);
+		assert_eq!(placed[2].transform.apply((0.0, -12.0)), (12.0 - LINE_HEIGHT, 12.0));
+	}
+	
+	#[test]
+	fn outer_transform_applies_last() {
+		let font = font();
+		let t = Transform::mirror_x().then(&Transform::translate(100.0, 0.0));
+		let placed = layout_str(&font, "!", &t);
+		
+		assert_eq!(placed[0].transform.apply((0.0, 0.0)), (95.0, 0.0));
+		assert_eq!(placed[0].strokes()[0][0], (95.0, -12.0));
+	}
+}
@@ -0,0 +1,332 @@
+//! LibreCAD's LFF fonts: plain text, a `[0041] A` line per glyph, then a
+//! line per polyline of `x,y` points separated by `;`.
+//!
+//! LFF has +y going up from the baseline, and x from the left edge of the
+//! glyph. Glyphs don't have widths; LibreCAD puts `LetterSpacing` after the
+//! rightmost point of each one, and uses `WordSpacing` for spaces.
+//!
+//! That means per-glyph widths don't survive a round trip. Left side
+//! bearings do, but every glyph comes back with the same right side
+//! bearing, so its advance is rebuilt from its ink and `LetterSpacing`.
+
+use std::fmt::Write;
+
+use crate::{HersheyChar, HersheyError, HersheyFont, Point};
+use crate::bezier::num;
+use crate::transform::Transform;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LffOptions {
+	/// How many LFF units one Hershey unit is. The default takes the bundled
+	/// fonts' 21 unit cap height to the usual 9 for LFF.
+	pub scale: f64,
+	
+	/// Where the baseline is, in Hershey units. It's 9 for the bundled fonts.
+	pub baseline: f64,
+	
+	/// Done to every glyph first, in Hershey units around its origin, when
+	/// writing. `LetterSpacing` and `WordSpacing` get stretched along with x.
+	/// Reading ignores it.
+	pub transform: Transform,
+}
+
+impl Default for LffOptions {
+	fn default() -> Self {
+		LffOptions {
+			scale: 9.0 / 21.0,
+			baseline: 9.0,
+			transform: Transform::IDENTITY,
+		}
+	}
+}
+
+/// Lowest and highest x of everything `chr` draws.
+fn x_range(chr: &HersheyChar) -> Option<(i8, i8)> {
+	let mut xs = chr.vertex_data.iter().flatten().map(|v| v.0);
+	let first = xs.next()?;
+	Some(xs.fold((first, first), |(lo, hi), x| (lo.min(x), hi.max(x))))
+}
+
+/// The most common right side bearing in `font`, in Hershey units, or the
+/// smallest of them if it's a tie. This is the `LetterSpacing` it gets.
+fn letter_spacing(font: &HersheyFont) -> i32 {
+	let mut bearings = font.char_map().iter()
+		.map(|&(_, i)| &font.chars[i])
+		.filter_map(|chr| Some(chr.right_hand as i32 - x_range(chr)?.1 as i32))
+		.collect::<Vec<_>>();
+	bearings.sort_unstable();
+	bearings.iter()
+		.max_by_key(|&&b| (bearings.iter().filter(|&&c| c == b).count(), -b))
+		.copied()
+		.unwrap_or(0)
+}
+
+/// Write `font` as an LFF font. Every glyph keeps its left side bearing,
+/// but there's only one right side bearing for the whole font: whichever
+/// one most glyphs have.
+pub fn to_lff(font: &HersheyFont, options: &LffOptions) -> String {
+	let map = font.char_map();
+	let letter_spacing = letter_spacing(font);
+	let word_spacing = font.glyph_for(' ').map_or(0, HersheyChar::advance);
+	// how much longer a horizontal line gets
+	let stretch = options.transform.apply_vector((1.0, 0.0)).0.abs();
+	
+	let mut lff = String::new();
+	writeln!(lff, "# Format:            LibreCAD Font 1").unwrap();
+	writeln!(lff, "# Creator:           hershey_reader").unwrap();
+	writeln!(lff, "# Version:           1").unwrap();
+	writeln!(lff, "# Name:              {}", font.name).unwrap();
+	writeln!(lff, "# Encoding:          UTF-8").unwrap();
+	writeln!(lff, "# LetterSpacing:     {}", num(letter_spacing as f64 * stretch * options.scale)).unwrap();
+	writeln!(lff, "# WordSpacing:       {}", num(word_spacing as f64 * stretch * options.scale)).unwrap();
+	writeln!(lff, "# LineSpacingFactor: 1").unwrap();
+	
+	for (c, i) in map {
+		// LibreCAD does spaces itself
+		if c == ' ' { continue; }
+		
+		let chr = &font.chars[i];
+		writeln!(lff, "\n[{:04X}] {}", c as u32, c).unwrap();
+		
+		let (left_hand, _) = chr.transformed_edges(&options.transform);
+		for stroke in chr.transformed(&options.transform) {
+			let mut points = stroke.iter()
+				.map(|&(x, y)| format!("{},{}", num((x - left_hand) * options.scale), num((options.baseline - y) * options.scale)))
+				.collect::<Vec<_>>();
+			// a polyline needs two points, even if it's a dot
+			if points.len() == 1 {
+				points.push(points[0].clone());
+			}
+			writeln!(lff, "{}", points.join(";")).unwrap();
+		}
+	}
+	
+	lff
+}
+
+/// Points along a bulged LFF segment, not counting the start. The bulge is
+/// the tangent of a quarter of the arc's angle, positive for anticlockwise.
+fn bulge_arc(from: Point, to: Point, bulge: f64) -> Vec<Point> {
+	let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+	let k = (1.0 - bulge * bulge) / (4.0 * bulge);
+	let center = ((from.0 + to.0) / 2.0 - dy * k, (from.1 + to.1) / 2.0 + dx * k);
+	let radius = (from.0 - center.0).hypot(from.1 - center.1);
+	
+	let start = (from.1 - center.1).atan2(from.0 - center.0);
+	let sweep = 4.0 * bulge.atan();
+	let steps = (sweep.abs() / (std::f64::consts::PI / 8.0)).ceil().max(1.0) as usize;
+	
+	(1..=steps).map(|i| {
+		if i == steps { return to; }
+		let a = start + sweep * i as f64 / steps as f64;
+		(center.0 + radius * a.cos(), center.1 + radius * a.sin())
+	}).collect()
+}
+
+/// One line of points, with any bulges flattened.
+fn parse_polyline(line: &str) -> Result<Vec<Point>, HersheyError> {
+	let mut points: Vec<Point> = Vec::new();
+	
+	for vertex in line.split(';').map(str::trim).filter(|v| !v.is_empty()) {
+		let mut parts = vertex.split(',').map(str::trim);
+		let mut coord = || parts.next()
+			.and_then(|v| v.parse::<f64>().ok())
+			.ok_or(HersheyError::InvalidLff);
+		let p = (coord()?, coord()?);
+		
+		let bulge = match parts.next() {
+			Some(b) if b.starts_with('A') => b[1..].parse::<f64>().map_err(|_| HersheyError::InvalidLff)?,
+			Some(_) => return Err(HersheyError::InvalidLff),
+			None => 0.0,
+		};
+		
+		match points.last() {
+			Some(&from) if bulge != 0.0 => points.extend(bulge_arc(from, p, bulge)),
+			_ => points.push(p),
+		}
+	}
+	
+	Ok(points)
+}
+
+/// Read an LFF font. Glyphs come out centred on their origin like Hershey
+/// glyphs are, with a [`unicode`](HersheyFont::unicode) table. Fails with
+/// [`HersheyError::OutOfRange`] if something doesn't fit in an `i8` at
+/// `options.scale`.
+pub fn from_lff(lff: &str, options: &LffOptions) -> Result<HersheyFont, HersheyError> {
+	let mut name = String::new();
+	let (mut letter_spacing, mut word_spacing) = (0.0, 0.0);
+	
+	// code point, polylines, and which glyph to copy if it's a `C` line
+	let mut glyphs: Vec<(u32, Vec<Vec<Point>>, Vec<u32>)> = Vec::new();
+	
+	for line in lff.lines().map(str::trim) {
+		if let Some(comment) = line.strip_prefix('#') {
+			if let Some((key, value)) = comment.split_once(':') {
+				let value = value.trim();
+				match key.trim() {
+					"Name" => name = value.to_string(),
+					"LetterSpacing" => letter_spacing = value.parse().map_err(|_| HersheyError::InvalidLff)?,
+					"WordSpacing" => word_spacing = value.parse().map_err(|_| HersheyError::InvalidLff)?,
+					_ => {}
+				}
+			}
+		} else if let Some(header) = line.strip_prefix('[') {
+			let code = header.split(']').next().unwrap_or_default().trim_start_matches('#');
+			let code = u32::from_str_radix(code, 16).map_err(|_| HersheyError::InvalidLff)?;
+			glyphs.push((code, Vec::new(), Vec::new()));
+		} else if !line.is_empty() {
+			let glyph = glyphs.last_mut().ok_or(HersheyError::InvalidLff)?;
+			match line.strip_prefix('C') {
+				Some(code) => glyph.2.push(u32::from_str_radix(code.trim(), 16).map_err(|_| HersheyError::InvalidLff)?),
+				None => glyph.1.push(parse_polyline(line)?),
+			}
+		}
+	}
+	
+	let fit = |v: f64| -> Result<i8, HersheyError> {
+		let v = v.round();
+		if (i8::MIN as f64..=i8::MAX as f64).contains(&v) { Ok(v as i8) } else { Err(HersheyError::OutOfRange) }
+	};
+	
+	let mut chars = Vec::new();
+	let mut unicode = Vec::new();
+	
+	// LibreCAD spaces things out itself, so there usually isn't one
+	if !glyphs.iter().any(|g| g.0 == ' ' as u32) {
+		let advance = fit(word_spacing / options.scale)?;
+		chars.push(HersheyChar {
+			id: 12345,
+			vertex_num: 0,
+			left_hand: -(advance / 2),
+			right_hand: advance - advance / 2,
+			vertex_data: Vec::new(),
+		});
+		unicode.push(Some(' '));
+	}
+	
+	for (code, lines, copies) in &glyphs {
+		let copied = copies.iter()
+			.filter_map(|c| glyphs.iter().find(|g| g.0 == *c))
+			.flat_map(|g| g.1.iter());
+		let lines = lines.iter().chain(copied).collect::<Vec<_>>();
+		
+		let right = lines.iter().flat_map(|l| l.iter()).map(|p| p.0).fold(0.0, f64::max);
+		let advance = fit((right + letter_spacing) / options.scale)?;
+		let left_hand = -(advance / 2);
+		
+		let mut vertex_data = Vec::new();
+		for line in lines {
+			if !vertex_data.is_empty() { vertex_data.push(None); }
+			
+			let mut run = Vec::new();
+			for &(x, y) in line {
+				let x = fit(x / options.scale + left_hand as f64)?;
+				let y = fit(options.baseline - y / options.scale)?;
+				run.push((x, y));
+			}
+			run.dedup();
+			vertex_data.extend(run.into_iter().map(Some));
+		}
+		
+		chars.push(HersheyChar {
+			id: 12345, // like the bundled fonts
+			vertex_num: vertex_data.len(),
+			left_hand,
+			right_hand: fit(left_hand as f64 + advance as f64)?,
+			vertex_data,
+		});
+		unicode.push(char::from_u32(*code));
+	}
+	
+	Ok(HersheyFont {
+		unicode: Some(unicode),
+		..HersheyFont::new(name, chars)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	
+	#[test]
+	fn writes_glyphs() {
+		let lff = to_lff(&test_font("futural"), &LffOptions::default());
+		
+		assert!(lff.contains("# Name:              futural\n"));
+		assert!(lff.contains("# WordSpacing:       6.857\n"));
+		assert!(!lff.contains("[0020]"));
+		// '-' goes from 4 to 22 of its 26 wide, on the baseline 9 units down
+		assert!(lff.contains("\n[002D] -\n1.714,3.857;9.429,3.857\n"));
+	}
+	
+	#[test]
+	fn transformed() {
+		let transform = Transform::mirror_x().then(&Transform::scale(2.0, 1.0));
+		let lff = to_lff(&test_font("futural"), &LffOptions { transform, ..LffOptions::default() });
+		
+		assert!(lff.contains("# WordSpacing:       13.714\n"));
+		assert!(lff.contains("\n[002D] -\n18.857,3.857;3.429,3.857\n"));
+	}
+	
+	#[test]
+	fn round_trip() -> Result<(), HersheyError> {
+		let font = test_font("futural");
+		let back = from_lff(&to_lff(&font, &LffOptions::default()), &LffOptions::default())?;
+		
+		assert_eq!(back.name, "futural");
+		assert_eq!(back.char_map(), font.char_map());
+		
+		// shapes and left bearings survive, but every glyph gets the one
+		// right bearing after its ink
+		let spacing = letter_spacing(&font);
+		for (a, b) in font.chars.iter().zip(&back.chars) {
+			let shift = b.left_hand - a.left_hand;
+			assert_eq!(
+				a.vertex_data.iter().map(|v| v.map(|(x, y)| (x + shift, y))).collect::<Vec<_>>(),
+				b.vertex_data,
+			);
+			
+			let advance = match x_range(a) {
+				Some((_, right)) => right as i32 - a.left_hand as i32 + spacing,
+				// only the space, which is WordSpacing
+				None => a.advance(),
+			};
+			assert_eq!(b.advance(), advance);
+		}
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn arcs_and_copies() -> Result<(), HersheyError> {
+		const LFF: &str = "# Name: Test\n# LetterSpacing: 2\n# WordSpacing: 6\n\n\
+			[0041] A\n0,0;0,4\n\n\
+			[00C4] Ä\nC0041\n1,6;1,6\n\n\
+			[006F] o\n0,0;4,0,A1;0,0,A1\n";
+		let options = LffOptions { scale: 1.0, ..LffOptions::default() };
+		let font = from_lff(LFF, &options)?;
+		
+		assert_eq!(font.name, "Test");
+		assert_eq!(font.glyph_for(' ').map(HersheyChar::advance), Some(6));
+		
+		let a = font.glyph_for('A').unwrap();
+		assert_eq!((a.left_hand, a.right_hand), (-1, 1));
+		assert_eq!(a.vertex_data, vec![Some((-1, 9)), Some((-1, 5))]);
+		
+		let umlaut = font.glyph_for('Ä').unwrap();
+		assert_eq!(umlaut.strokes().len(), 2);
+		
+		// two half circles
+		let o = font.glyph_for('o').unwrap();
+		let lowest = o.vertex_data.iter().flatten().map(|v| v.1).max();
+		assert_eq!(lowest, Some(11));
+		assert_eq!(o.advance(), 6);
+		
+		assert_eq!(from_lff("0,0;1,1\n", &options), Err(HersheyError::InvalidLff));
+		assert_eq!(from_lff("[0041]\n0,0;1000,0\n", &options), Err(HersheyError::OutOfRange));
+		
+		Ok(())
+	}
+}
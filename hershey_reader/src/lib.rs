@@ -1,9 +1,46 @@
 use core::num::ParseIntError;
 
+pub mod transform;
+pub mod layout;
+pub mod path;
+pub mod plot;
+pub mod cleanup;
+pub mod polyline;
+pub mod bezier;
+pub mod outline;
+pub mod ttf;
+pub mod svg_font;
+pub mod bgi;
+pub mod lff;
+pub mod shp;
+pub mod pack;
+pub mod codegen;
+pub mod unicode;
+pub mod symbols;
+pub mod stack;
+pub mod accents;
+pub mod markup;
+pub mod math;
+pub mod rich;
+pub mod mono;
+#[cfg(feature = "serde")]
+pub mod json;
+
+use transform::Transform;
+
+/// A point with float precision, in the same units (and with the same
+/// +y-is-down orientation) as [`HersheyChar::vertex_data`].
+pub type Point = (f64, f64);
+
+/// One pen-down run of connected points.
+pub type Stroke = Vec<Point>;
+
 /// This represents a single character in a Hershey font.
 /// This won't actually reliably have *the actual codepoint it represents*
 /// anywhere in its data, but it does have vertices!
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "json::JsonChar", from = "json::JsonChar"))]
 pub struct HersheyChar {
 	/// Kinda... not used?
 	/// 
@@ -41,6 +78,25 @@ pub enum HersheyError {
 	TooShort,
 	MalformedVertices,
 	Parse(ParseIntError),
+	/// Path data that doesn't parse, or uses a command that isn't supported.
+	InvalidSvgPath,
+	/// There's no `<font>` with any `<glyph>`s in it.
+	NoSvgFont,
+	/// An SVG font's `units-per-em` isn't a positive number.
+	InvalidUnitsPerEm,
+	/// A `.CHR` file that's cut short or has a broken header.
+	InvalidChr,
+	/// An LFF line that isn't a comment, a glyph header, or a polyline.
+	InvalidLff,
+	/// Something doesn't fit in the format it's being written to.
+	OutOfRange,
+	/// JSON that doesn't fit the layout in the `json` module.
+	InvalidJson(String),
+	/// A font pack with the wrong header or checksum, or that points
+	/// outside itself.
+	InvalidPack,
+	/// Markup that doesn't parse. Has the byte offset of where it went wrong.
+	InvalidMarkup(usize),
 }
 
 impl HersheyChar {
@@ -113,10 +169,161 @@ impl HersheyChar {
 		})
 	}
 	
+	/// Split `vertex_data` at the pen-ups, giving each connected run
+	/// as its own stroke. Lone points are kept as 1-point strokes.
+	pub fn strokes(&self) -> Vec<Stroke> {
+		self.transformed(&Transform::IDENTITY)
+	}
+	
+	/// Same as [`strokes`](Self::strokes), but every point goes through `t` first.
+	pub fn transformed(&self, t: &Transform) -> Vec<Stroke> {
+		self.vertex_data
+			.split(Option::is_none)
+			.filter(|run| !run.is_empty())
+			.map(|run| run.iter()
+				.flatten()
+				.map(|&(x, y)| t.apply((x as f64, y as f64)))
+				.collect())
+			.collect()
+	}
+	
+	/// Horizontal distance from this character's origin to the next one's.
+	pub fn advance(&self) -> i32 {
+		self.right_hand as i32 - self.left_hand as i32
+	}
+	
+	/// Where the left and right edges end up after `t`, for exporters that
+	/// bake a transform into the glyphs. If it mirrors x, the edges swap over.
+	pub(crate) fn transformed_edges(&self, t: &Transform) -> (f64, f64) {
+		let left = t.apply((self.left_hand as f64, 0.0)).0;
+		let right = t.apply((self.right_hand as f64, 0.0)).0;
+		if t.0[0][0] < 0.0 { (right, left) } else { (left, right) }
+	}
+	
 	/// Please give this only valid `char`s lol
 	const fn parse_ascii_ofs(c: char) -> i8 {
 		(c as i8) - (b'R' as i8) // ('R' is 82 in ASCII)
 	}
+	
+	/// The other way around from [`parse_ascii_ofs`](Self::parse_ascii_ofs).
+	fn to_ascii_ofs(v: i8) -> Result<char, HersheyError> {
+		let c = v as i32 + b'R' as i32;
+		if (b' ' as i32..=b'~' as i32).contains(&c) { Ok(c as u8 as char) }
+		else { Err(HersheyError::OutOfRange) }
+	}
+	
+	/// Write this character back out as a line of a .jhf file, the way the
+	/// bundled fonts do it. The vertex count comes from `vertex_data`, not
+	/// `vertex_num`.
+	/// 
+	/// Fails with [`HersheyError::OutOfRange`] if the id or vertex count
+	/// don't fit in their columns, or a coordinate isn't a printable character.
+	pub fn to_jhf(&self) -> Result<String, HersheyError> {
+		let count = self.vertex_data.len() + 1;
+		if self.id > 99999 || count > 999 { return Err(HersheyError::OutOfRange); }
+		
+		let mut s = format!("{:5}{:3}", self.id, count);
+		s.push(Self::to_ascii_ofs(self.left_hand)?);
+		s.push(Self::to_ascii_ofs(self.right_hand)?);
+		
+		for v in &self.vertex_data {
+			match *v {
+				None => s.push_str(" R"),
+				// that's the pen-up, it'd read back as one
+				Some((-50, 0)) => return Err(HersheyError::OutOfRange),
+				Some((x, y)) => {
+					s.push(Self::to_ascii_ofs(x)?);
+					s.push(Self::to_ascii_ofs(y)?);
+				}
+			}
+		}
+		
+		Ok(s)
+	}
+}
+
+/// A whole .jhf file's worth of characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "json::JsonFont", try_from = "json::JsonFont"))]
+pub struct HersheyFont {
+	/// Usually the file name.
+	pub name: String,
+	
+	/// Characters in file order. For the bundled fonts, that's ASCII order
+	/// starting from the space.
+	pub chars: Vec<HersheyChar>,
+	
+	/// Which character each of [`chars`](Self::chars) is, for fonts that
+	/// aren't in ASCII order. `None` means they are.
+	/// 
+	/// The bundled Greek, Cyrillic, math and Japanese fonts can get theirs from
+	/// [`with_builtin_unicode`](Self::with_builtin_unicode).
+	pub unicode: Option<Vec<Option<char>>>,
+}
+
+impl HersheyFont {
+	pub fn new(name: impl Into<String>, chars: Vec<HersheyChar>) -> Self {
+		HersheyFont { name: name.into(), chars, unicode: None }
+	}
+	
+	/// Parse every line of a .jhf file.
+	pub fn new_from_str(name: impl Into<String>, s: &str) -> Result<Self, HersheyError> {
+		let chars = s.trim_end()
+			.lines()
+			.map(HersheyChar::new_from_str)
+			.collect::<Result<_, _>>()?;
+		
+		Ok(Self::new(name, chars))
+	}
+	
+	/// Write every character out as a .jhf file, one line each.
+	/// [`unicode`](Self::unicode) doesn't go anywhere, since .jhf files don't have it.
+	pub fn to_jhf(&self) -> Result<String, HersheyError> {
+		let mut s = String::new();
+		for chr in &self.chars {
+			s.push_str(&chr.to_jhf()?);
+			s.push('\n');
+		}
+		Ok(s)
+	}
+	
+	/// Look up a character, going by [`unicode`](Self::unicode) if there is
+	/// one, and ASCII order if there isn't. In ASCII order, control
+	/// characters get the space.
+	pub fn glyph_for(&self, c: char) -> Option<&HersheyChar> {
+		if let Some(unicode) = &self.unicode {
+			let i = unicode.iter().position(|&u| u == Some(c))?;
+			return self.chars.get(i);
+		}
+		
+		if !c.is_ascii() { return None; }
+		
+		self.chars.get((c as u8).saturating_sub(b' ') as usize)
+	}
+	
+	/// Every character [`glyph_for`](Self::glyph_for) has a proper glyph for,
+	/// with that glyph's index in [`chars`](Self::chars).
+	pub fn char_map(&self) -> Vec<(char, usize)> {
+		match &self.unicode {
+			Some(unicode) => unicode.iter()
+				.take(self.chars.len())
+				.enumerate()
+				.filter_map(|(i, u)| Some(((*u)?, i)))
+				.collect(),
+			None => (' '..='~')
+				.zip(0..self.chars.len())
+				.collect(),
+		}
+	}
+}
+
+/// One of the fonts in `fonts/`, by file name without the `.jhf`. The
+/// tests all run from this crate's directory.
+#[cfg(test)]
+pub(crate) fn test_font(name: &str) -> HersheyFont {
+	let jhf = std::fs::read_to_string(format!("../fonts/{}.jhf", name)).unwrap();
+	HersheyFont::new_from_str(name, &jhf).unwrap()
 }
 
 #[cfg(test)]
@@ -186,6 +393,62 @@ mod tests {
 		Ok(())
 	}
 	
+	#[test]
+	fn strokes_split_at_pen_up() -> Result<(), HersheyError> {
+		// Exclamation mark from futural.jhf
+		const CHR: &str = "12345  9MWRFRT RRYQZR[SZRY";
+		
+		let c = HersheyChar::new_from_str(CHR)?;
+		let strokes = c.strokes();
+		
+		assert_eq!(strokes.len(), 2);
+		assert_eq!(strokes[0], vec![(0.0, -12.0), (0.0, 2.0)]);
+		assert_eq!(strokes[1].len(), 5);
+		assert_eq!(c.advance(), 10);
+		
+		let moved = c.transformed(&Transform::translate(1.0, 1.0));
+		assert_eq!(moved[0][0], (1.0, -11.0));
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn writes_jhf() -> Result<(), HersheyError> {
+		const CHR: &str = "12345  9MWRFRT RRYQZR[SZRY";
+		assert_eq!(HersheyChar::new_from_str(CHR)?.to_jhf()?, CHR);
+		
+		// a bit messy, but it reads back the same
+		let c = HersheyChar::new_from_str("3    1 JZ")?;
+		assert_eq!(c.to_jhf()?, "    3  1JZ");
+		assert_eq!(HersheyChar::new_from_str(&c.to_jhf()?)?, c);
+		
+		let wide = HersheyChar { left_hand: -60, ..c.clone() };
+		assert_eq!(wide.to_jhf(), Err(HersheyError::OutOfRange));
+		let sneaky = HersheyChar { vertex_data: vec![Some((-50, 0))], ..c };
+		assert_eq!(sneaky.to_jhf(), Err(HersheyError::OutOfRange));
+		
+		Ok(())
+	}
+	
+	// Same files in, same files out.
+	#[test]
+	fn rewrite_all_fonts() {
+		use std::fs::{read_dir, read_to_string};
+		use std::ffi::OsStr;
+		
+		for entry in read_dir("../fonts/").unwrap() {
+			let path = entry.unwrap().path();
+			
+			if path.is_file()
+			&& path.extension().and_then(OsStr::to_str) == Some("jhf") {
+				let jhf = read_to_string(&path).unwrap();
+				let font = HersheyFont::new_from_str("", &jhf).unwrap();
+				
+				assert_eq!(font.to_jhf().unwrap(), jhf, "{:?}", path);
+			}
+		}
+	}
+	
 	// Make sure I can read every font without error.
 	// I panic if I can't find my files. That's fine.
 	#[test]
@@ -197,7 +460,7 @@ mod tests {
 			let path = entry.unwrap().path();
 			
 			if path.is_file()
-			&& path.extension().map(OsStr::to_str).flatten() == Some("jhf") {
+			&& path.extension().and_then(OsStr::to_str) == Some("jhf") {
 				let chr = read_to_string(path).unwrap();
 				let chr = chr.trim();
 				
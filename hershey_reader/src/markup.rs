@@ -0,0 +1,320 @@
+//! A little markup for switching fonts and sizes partway through a string,
+//! like the escape codes in Hershey's own typesetting. It's enough for
+//! labels like `E = mc^{2}` or `\f{greek}a\f{}-particle`.
+//!
+//! - `\f{greekc}` switches to another font, by name. `\f{}` goes back to
+//!   the default one.
+//! - `\s{0.7}` scales the text, relative to the group it's in. `\s{}`
+//!   puts it back.
+//! - `^{...}` and `_{...}` are superscript and subscript. Without the
+//!   braces, like `x^2`, they only take the next character.
+//! - `{...}` is a group. Switches inside it stop at the `}`.
+//! - `\\`, `\{`, `\}`, `\^` and `\_` are just those characters.
+//!
+//! Font names aren't looked up until layout, so they can be anything. The
+//! .jhf file names make sense, though.
+
+use core::iter::Peekable;
+use core::str::CharIndices;
+
+use crate::HersheyError;
+use crate::layout::{Glyphs, PlacedChar, BASELINE, LINE_HEIGHT};
+use crate::transform::Transform;
+
+/// How much smaller superscripts and subscripts are than the text they're on.
+pub const SCRIPT_SIZE: f64 = 0.6;
+
+/// How far a superscript's baseline goes up, in font units, at size 1.
+/// Puts the top of a capital about level with the top of the one it's on.
+pub const SUPERSCRIPT_RISE: f64 = 9.0;
+
+/// How far a subscript's baseline goes down, in font units, at size 1.
+pub const SUBSCRIPT_DROP: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+	/// Which font, by name. `None` is whatever the default one is.
+	pub font: Option<String>,
+	
+	/// Multiplies the glyphs' size, and how far they move the pen.
+	pub size: f64,
+	
+	/// How far the baseline is raised, in font units. Negative lowers it.
+	pub rise: f64,
+}
+
+impl Default for Style {
+	fn default() -> Self {
+		Style { font: None, size: 1.0, rise: 0.0 }
+	}
+}
+
+/// Some text that's all in one [`Style`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+	pub text: String,
+	pub style: Style,
+}
+
+/// Everything inside one pair of braces.
+struct Group {
+	style: Style,
+	/// What `\s{1}` means in here.
+	size: f64,
+	/// For `^2` and `_2`, which end after one character.
+	one_char: bool,
+	/// Where it started, for errors.
+	at: usize,
+}
+
+/// The `{...}` after an `\f` or `\s`. Errors point at the `\`.
+fn argument(chars: &mut Peekable<CharIndices>, at: usize) -> Result<String, HersheyError> {
+	if !matches!(chars.next(), Some((_, '{'))) {
+		return Err(HersheyError::InvalidMarkup(at));
+	}
+	
+	let mut arg = String::new();
+	for (_, c) in chars {
+		if c == '}' { return Ok(arg); }
+		arg.push(c);
+	}
+	Err(HersheyError::InvalidMarkup(at))
+}
+
+/// Split `markup` up into runs of differently styled text. Fails with
+/// [`HersheyError::InvalidMarkup`] and the byte offset of the problem,
+/// for unknown escapes, unmatched braces, or sizes that aren't positive
+/// numbers.
+pub fn parse(markup: &str) -> Result<Vec<Run>, HersheyError> {
+	let mut runs: Vec<Run> = Vec::new();
+	let mut stack = vec![Group { style: Style::default(), size: 1.0, one_char: false, at: 0 }];
+	let mut chars = markup.char_indices().peekable();
+	
+	while let Some((at, c)) = chars.next() {
+		let outermost = stack.len() == 1;
+		let top = stack.last_mut().unwrap();
+		
+		let c = match c {
+			'\\' => match chars.next() {
+				Some((_, c @ ('\\' | '{' | '}' | '^' | '_'))) => c,
+				Some((_, 'f')) => {
+					let name = argument(&mut chars, at)?;
+					top.style.font = if name.is_empty() { None } else { Some(name) };
+					continue;
+				}
+				Some((_, 's')) => {
+					let size = argument(&mut chars, at)?;
+					let size = if size.trim().is_empty() { 1.0 } else {
+						size.trim().parse::<f64>().ok()
+							.filter(|s| s.is_finite() && *s > 0.0)
+							.ok_or(HersheyError::InvalidMarkup(at))?
+					};
+					top.style.size = top.size * size;
+					continue;
+				}
+				_ => return Err(HersheyError::InvalidMarkup(at)),
+			},
+			'^' | '_' => {
+				let style = &top.style;
+				let shift = if c == '^' { SUPERSCRIPT_RISE } else { -SUBSCRIPT_DROP };
+				let style = Style {
+					font: style.font.clone(),
+					size: style.size * SCRIPT_SIZE,
+					rise: style.rise + shift * style.size,
+				};
+				
+				let braced = matches!(chars.peek(), Some((_, '{')));
+				if braced { chars.next(); }
+				stack.push(Group { size: style.size, style, one_char: !braced, at });
+				continue;
+			}
+			'{' => {
+				let style = top.style.clone();
+				stack.push(Group { size: style.size, style, one_char: false, at });
+				continue;
+			}
+			'}' => {
+				if outermost || top.one_char {
+					return Err(HersheyError::InvalidMarkup(at));
+				}
+				stack.pop();
+				continue;
+			}
+			c => c,
+		};
+		
+		let style = &top.style;
+		match runs.last_mut() {
+			Some(run) if run.style == *style => run.text.push(c),
+			_ => runs.push(Run { text: c.to_string(), style: style.clone() }),
+		}
+		
+		while stack.last().unwrap().one_char {
+			stack.pop();
+		}
+	}
+	
+	match stack.last() {
+		Some(group) if stack.len() > 1 => Err(HersheyError::InvalidMarkup(group.at)),
+		_ => Ok(runs),
+	}
+}
+
+/// Every font name the runs ask for, once each, so they can be loaded.
+pub fn font_names(runs: &[Run]) -> Vec<&str> {
+	let mut names = Vec::new();
+	for name in runs.iter().filter_map(|run| run.style.font.as_deref()) {
+		if !names.contains(&name) { names.push(name); }
+	}
+	names
+}
+
+/// Lay out `runs` like [`layout_str`](crate::layout::layout_str) would,
+/// getting each run's font from `fonts`. Runs with no font, or one `fonts`
+/// doesn't know, use `default`. [`PlacedChar::index`] counts through the
+/// text of all the runs, not the markup.
+pub fn layout_runs<'a, F, L>(default: &'a F, fonts: L, runs: &[Run], t: &Transform) -> Vec<PlacedChar<'a>>
+where
+	F: Glyphs + ?Sized,
+	L: Fn(&str) -> Option<&'a F>,
+{
+	let mut placed = Vec::new();
+	let mut line = 0;
+	let mut pen_x = 0.0;
+	let mut index = 0;
+	
+	for run in runs {
+		let font = run.style.font.as_deref()
+			.and_then(&fonts)
+			.unwrap_or(default);
+		
+		for ch in run.text.chars() {
+			if ch == '\n' {
+				line += 1;
+				pen_x = 0.0;
+			} else if let Some((chr, scale)) = font.sized_glyph(ch) {
+				let scale = scale * run.style.size;
+				// scaled around the baseline, so every size sits on the same line
+				let origin = Transform::translate(-chr.left_hand as f64, -BASELINE)
+					.then(&Transform::uniform_scale(scale))
+					.then(&Transform::translate(pen_x, line as f64 * LINE_HEIGHT + BASELINE - run.style.rise));
+				
+				placed.push(PlacedChar {
+					chr,
+					transform: origin.then(t),
+					line, index,
+				});
+				pen_x += chr.advance() as f64 * scale;
+			}
+			index += 1;
+		}
+	}
+	
+	placed
+}
+
+/// [`parse`] and [`layout_runs`] in one go.
+pub fn layout_markup<'a, F, L>(default: &'a F, fonts: L, markup: &str, t: &Transform) -> Result<Vec<PlacedChar<'a>>, HersheyError>
+where
+	F: Glyphs + ?Sized,
+	L: Fn(&str) -> Option<&'a F>,
+{
+	Ok(layout_runs(default, fonts, &parse(markup)?, t))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	use std::collections::HashMap;
+	
+	fn run(text: &str, font: Option<&str>, size: f64, rise: f64) -> Run {
+		Run {
+			text: text.into(),
+			style: Style { font: font.map(Into::into), size, rise },
+		}
+	}
+	
+	#[test]
+	fn parses() {
+		assert_eq!(parse("E = mc^{2}"), Ok(vec![
+			run("E = mc", None, 1.0, 0.0),
+			run("2", None, 0.6, 9.0),
+		]));
+		assert_eq!(parse("x_i^2 + y"), parse("x_{i}^{2} + y"));
+		assert_eq!(parse("\\f{greek}a\\f{}-particle"), Ok(vec![
+			run("a", Some("greek"), 1.0, 0.0),
+			run("-particle", None, 1.0, 0.0),
+		]));
+		assert_eq!(parse("{\\s{2}big}\\s{0.5}^{\\s{}\\f{symbolic}x}\\{\\}"), Ok(vec![
+			run("big", None, 2.0, 0.0),
+			run("x", Some("symbolic"), 0.3, 4.5),
+			run("{}", None, 0.5, 0.0),
+		]));
+		assert_eq!(parse(""), Ok(vec![]));
+		
+		assert_eq!(parse("a}"), Err(HersheyError::InvalidMarkup(1)));
+		assert_eq!(parse("a^{b"), Err(HersheyError::InvalidMarkup(1)));
+		assert_eq!(parse("a^"), Err(HersheyError::InvalidMarkup(1)));
+		assert_eq!(parse("\\q"), Err(HersheyError::InvalidMarkup(0)));
+		assert_eq!(parse("ab\\s{-1}"), Err(HersheyError::InvalidMarkup(2)));
+		assert_eq!(parse("\\f{oops"), Err(HersheyError::InvalidMarkup(0)));
+		
+		let runs = parse("\\f{greek}a\\f{mathlow}b\\f{greek}c").unwrap();
+		assert_eq!(font_names(&runs), vec!["greek", "mathlow"]);
+	}
+	
+	#[test]
+	fn lays_out() {
+		let fonts: HashMap<_, _> = ["futural", "greek"].iter()
+			.map(|&name| (name, test_font(name)))
+			.collect();
+		let futural = &fonts["futural"];
+		let lookup = |name: &str| fonts.get(name);
+		
+		let placed = layout_markup(futural, lookup, "\\f{greek}a\\f{nope}-b^2\nc", &Transform::IDENTITY).unwrap();
+		assert_eq!(placed.len(), 5);
+		assert_eq!(placed[0].chr, &fonts["greek"].chars[(b'a' - b' ') as usize]);
+		assert_eq!(placed[1].chr, futural.glyph_for('-').unwrap());
+		
+		// the 2 is small, raised, and right after the b
+		let b = futural.glyph_for('b').unwrap();
+		let two = futural.glyph_for('2').unwrap();
+		let b_right = placed[2].transform.apply((b.right_hand as f64, 0.0)).0;
+		assert_eq!(placed[3].transform.apply((two.left_hand as f64, BASELINE)), (b_right, BASELINE - SUPERSCRIPT_RISE));
+		let width = placed[3].transform.apply((1.0, 0.0)).0 - placed[3].transform.apply((0.0, 0.0)).0;
+		assert!((width - SCRIPT_SIZE).abs() < 1e-9);
+		
+		assert_eq!((placed[4].line, placed[4].index), (1, 5));
+		assert!(layout_markup(futural, lookup, "{", &Transform::IDENTITY).is_err());
+	}
+	
+	/// Top and bottom of everything `placed[i]` draws.
+	fn ink(placed: &[PlacedChar], i: usize) -> (f64, f64) {
+		placed[i].strokes().iter().flatten()
+			.fold((f64::INFINITY, f64::NEG_INFINITY), |(top, bottom), &(_, y)| (top.min(y), bottom.max(y)))
+	}
+	
+	#[test]
+	fn sizes_share_a_baseline() {
+		let futural = test_font("futural");
+		let none = |_: &str| None;
+		
+		// X sits right on the baseline and goes up to the cap height
+		let placed = layout_markup(&futural, none, "X\\s{2}X^X_X", &Transform::IDENTITY).unwrap();
+		let (cap_top, baseline) = ink(&placed, 0);
+		assert_eq!(baseline, BASELINE);
+		
+		// twice as big, but on the same line
+		assert_eq!(ink(&placed, 1), (BASELINE - 2.0 * (baseline - cap_top), BASELINE));
+		
+		// scripts on the big X: their baselines move by the documented
+		// amount, times the size of the thing they're on
+		let close = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9;
+		let script_height = 2.0 * SCRIPT_SIZE * (baseline - cap_top);
+		let sup = BASELINE - 2.0 * SUPERSCRIPT_RISE;
+		assert!(close(ink(&placed, 2), (sup - script_height, sup)));
+		let sub = BASELINE + 2.0 * SUBSCRIPT_DROP;
+		assert!(close(ink(&placed, 3), (sub - script_height, sub)));
+	}
+}
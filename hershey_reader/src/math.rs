@@ -0,0 +1,418 @@
+//! Laying out formulas from a little bit of TeX: `\frac{a}{b}`,
+//! `\sqrt{x}`, `^` and `_`, `\sum`, `\prod` and `\int`, and Greek letters by
+//! name (`\alpha`, `\Omega`). Spaces are ignored, like in TeX.
+//!
+//! Everything comes out of one [`Glyphs`], so use a
+//! [`FontStack`](crate::stack::FontStack) with the Greek and math fonts
+//! after the text one (all with their
+//! [`unicode`](crate::HersheyFont::with_builtin_unicode) maps). Fraction
+//! bars and radicals aren't glyphs, they're drawn as extra strokes.
+
+use core::iter::Peekable;
+use core::str::CharIndices;
+
+use crate::{HersheyError, Point, Stroke};
+use crate::layout::{Glyphs, BASELINE};
+use crate::markup::{SCRIPT_SIZE, SUBSCRIPT_DROP, SUPERSCRIPT_RISE};
+use crate::transform::Transform;
+
+/// Space on either side of `=`, `+` and the like.
+const OP_SPACE: f64 = 3.0;
+
+/// Space between a fraction bar and the things on it, or under a limit.
+const GAP: f64 = 3.0;
+
+const GREEK: &[(&str, char)] = &[
+	("alpha", 'α'), ("beta", 'β'), ("gamma", 'γ'), ("delta", 'δ'), ("epsilon", 'ε'),
+	("zeta", 'ζ'), ("eta", 'η'), ("theta", 'θ'), ("iota", 'ι'), ("kappa", 'κ'),
+	("lambda", 'λ'), ("mu", 'μ'), ("nu", 'ν'), ("xi", 'ξ'), ("omicron", 'ο'),
+	("pi", 'π'), ("rho", 'ρ'), ("sigma", 'σ'), ("tau", 'τ'), ("upsilon", 'υ'),
+	("phi", 'φ'), ("chi", 'χ'), ("psi", 'ψ'), ("omega", 'ω'),
+	("Gamma", 'Γ'), ("Delta", 'Δ'), ("Theta", 'Θ'), ("Lambda", 'Λ'), ("Xi", 'Ξ'),
+	("Pi", 'Π'), ("Sigma", 'Σ'), ("Upsilon", 'Υ'), ("Phi", 'Φ'), ("Psi", 'Ψ'),
+	("Omega", 'Ω'),
+];
+
+/// Commands for symbols that get [`OP_SPACE`] around them.
+const OPERATORS: &[(&str, char)] = &[
+	("pm", '±'), ("mp", '∓'), ("times", '×'), ("cdot", '⋅'), ("div", '÷'),
+	("le", '≤'), ("leq", '≤'), ("ge", '≥'), ("geq", '≥'), ("ne", '≠'), ("neq", '≠'),
+	("equiv", '≡'), ("propto", '∝'), ("to", '→'), ("rightarrow", '→'), ("leftarrow", '←'),
+	("in", '∈'), ("subset", '⊂'), ("supset", '⊃'), ("cup", '∪'), ("cap", '∩'),
+];
+
+/// Commands for symbols that don't.
+const SYMBOLS: &[(&str, char)] = &[
+	("infty", '∞'), ("partial", '∂'), ("nabla", '∇'), ("exists", '∃'),
+	("aleph", 'ℵ'), ("angle", '∠'), ("perp", '⊥'), ("therefore", '∴'),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+	Char(char),
+	/// Something like `=`, that wants some space around it.
+	Op(char),
+	/// `\sum` and friends.
+	Big(char),
+	Row(Vec<Node>),
+	Frac(Box<Node>, Box<Node>),
+	Sqrt(Box<Node>),
+	Scripts {
+		base: Box<Node>,
+		sup: Option<Box<Node>>,
+		sub: Option<Box<Node>>,
+	},
+}
+
+struct Parser<'s> {
+	chars: Peekable<CharIndices<'s>>,
+	len: usize,
+}
+
+impl Parser<'_> {
+	fn skip_spaces(&mut self) {
+		while matches!(self.chars.peek(), Some((_, c)) if c.is_whitespace()) {
+			self.chars.next();
+		}
+	}
+	
+	fn at(&mut self) -> usize {
+		self.chars.peek().map_or(self.len, |&(at, _)| at)
+	}
+	
+	/// Everything up to the `}` that closes the group opened at `open`, or
+	/// the end if there's no `open`.
+	fn row(&mut self, open: Option<usize>) -> Result<Node, HersheyError> {
+		let mut row = Vec::new();
+		
+		loop {
+			self.skip_spaces();
+			let at = self.at();
+			
+			match self.chars.peek().map(|&(_, c)| c) {
+				None => return match open {
+					Some(open) => Err(HersheyError::InvalidMarkup(open)),
+					None => Ok(Node::Row(row)),
+				},
+				Some('}') => {
+					self.chars.next();
+					return match open {
+						Some(_) => Ok(Node::Row(row)),
+						None => Err(HersheyError::InvalidMarkup(at)),
+					};
+				}
+				Some(c @ ('^' | '_')) => {
+					self.chars.next();
+					let script = Box::new(self.atom()?);
+					let (base, mut sup, mut sub) = match row.pop() {
+						Some(Node::Scripts { base, sup, sub }) => (base, sup, sub),
+						Some(node) => (Box::new(node), None, None),
+						None => (Box::new(Node::Row(Vec::new())), None, None),
+					};
+					
+					let slot = if c == '^' { &mut sup } else { &mut sub };
+					if slot.is_some() { return Err(HersheyError::InvalidMarkup(at)); }
+					*slot = Some(script);
+					row.push(Node::Scripts { base, sup, sub });
+				}
+				Some(_) => row.push(self.atom()?),
+			}
+		}
+	}
+	
+	/// One character, command or `{...}` group.
+	fn atom(&mut self) -> Result<Node, HersheyError> {
+		self.skip_spaces();
+		let (at, c) = self.chars.next().ok_or(HersheyError::InvalidMarkup(self.len))?;
+		
+		match c {
+			'{' => self.row(Some(at)),
+			'}' | '^' | '_' => Err(HersheyError::InvalidMarkup(at)),
+			'+' | '-' | '=' | '<' | '>' => Ok(Node::Op(c)),
+			'\\' => {
+				let mut name = String::new();
+				while let Some(&(_, c)) = self.chars.peek() {
+					if !c.is_ascii_alphabetic() { break; }
+					name.push(c);
+					self.chars.next();
+				}
+				
+				if name.is_empty() {
+					// `\{`, `\}` and so on
+					return match self.chars.next() {
+						Some((_, c)) if !c.is_alphanumeric() => Ok(Node::Char(c)),
+						_ => Err(HersheyError::InvalidMarkup(at)),
+					};
+				}
+				
+				let find = |table: &[(&str, char)]| table.iter().find(|&&(n, _)| n == name).map(|&(_, c)| c);
+				
+				Ok(match name.as_str() {
+					"frac" => Node::Frac(Box::new(self.atom()?), Box::new(self.atom()?)),
+					"sqrt" => Node::Sqrt(Box::new(self.atom()?)),
+					"sum" => Node::Big('∑'),
+					"prod" => Node::Big('∏'),
+					"int" => Node::Big('∫'),
+					_ => if let Some(c) = find(GREEK).or_else(|| find(SYMBOLS)) {
+						Node::Char(c)
+					} else if let Some(c) = find(OPERATORS) {
+						Node::Op(c)
+					} else {
+						return Err(HersheyError::InvalidMarkup(at));
+					},
+				})
+			}
+			c => Ok(Node::Char(c)),
+		}
+	}
+}
+
+/// Some laid out math: strokes with their origin on the left end of the
+/// math axis, like a glyph's, and how much room they take up.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Formula {
+	pub strokes: Vec<Stroke>,
+	
+	/// How far it goes to the right.
+	pub width: f64,
+	
+	/// The highest and lowest it goes. `top` is negative, since +y is down.
+	pub top: f64,
+	pub bottom: f64,
+}
+
+impl Formula {
+	/// Put `other` in with this, `(dx, dy)` along. Leaves `width` alone.
+	fn add(&mut self, other: Formula, (dx, dy): Point) {
+		let t = Transform::translate(dx, dy);
+		self.strokes.extend(other.strokes.into_iter()
+			.map(|stroke| stroke.into_iter().map(|p| t.apply(p)).collect()));
+		self.top = self.top.min(other.top + dy);
+		self.bottom = self.bottom.max(other.bottom + dy);
+	}
+	
+	/// Bigger or smaller, around the origin.
+	fn scaled(self, s: f64) -> Formula {
+		let t = Transform::uniform_scale(s);
+		Formula {
+			strokes: self.strokes.into_iter()
+				.map(|stroke| stroke.into_iter().map(|p| t.apply(p)).collect())
+				.collect(),
+			width: self.width * s,
+			top: self.top * s,
+			bottom: self.bottom * s,
+		}
+	}
+	
+	fn glyph<F: Glyphs + ?Sized>(font: &F, c: char) -> Formula {
+		let (chr, scale) = match font.sized_glyph(c) {
+			Some(it) => it,
+			None => return Formula::default(),
+		};
+		
+		let strokes = chr.transformed(&Transform::translate(-chr.left_hand as f64, 0.0)
+			.then(&Transform::uniform_scale(scale)));
+		let ys = || strokes.iter().flatten().map(|&(_, y)| y);
+		
+		Formula {
+			width: chr.advance() as f64 * scale,
+			top: ys().fold(0.0, f64::min),
+			bottom: ys().fold(0.0, f64::max),
+			strokes,
+		}
+	}
+	
+	fn row<F: Glyphs + ?Sized>(font: &F, nodes: &[Node]) -> Formula {
+		let mut row = Formula::default();
+		
+		for (i, node) in nodes.iter().enumerate() {
+			if let Node::Op(_) = node {
+				if i > 0 { row.width += OP_SPACE; }
+			}
+			
+			let part = Formula::node(font, node);
+			let width = part.width;
+			row.add(part, (row.width, 0.0));
+			row.width += width;
+			
+			if let Node::Op(_) = node {
+				if i > 0 { row.width += OP_SPACE; }
+			}
+		}
+		
+		row
+	}
+	
+	fn node<F: Glyphs + ?Sized>(font: &F, node: &Node) -> Formula {
+		match node {
+			// mathlow's ∑, ∏ and ∫ are already display sized
+			Node::Char(c) | Node::Op(c) | Node::Big(c) => Formula::glyph(font, *c),
+			Node::Row(nodes) => Formula::row(font, nodes),
+			Node::Frac(num, den) => {
+				let (num, den) = (Formula::node(font, num), Formula::node(font, den));
+				let width = num.width.max(den.width) + 2.0 * GAP;
+				
+				let mut frac = Formula {
+					strokes: vec![vec![(0.0, 0.0), (width, 0.0)]],
+					width,
+					top: 0.0,
+					bottom: 0.0,
+				};
+				let (num_x, den_x) = ((width - num.width) / 2.0, (width - den.width) / 2.0);
+				let (num_y, den_y) = (-GAP - num.bottom, GAP - den.top);
+				frac.add(num, (num_x, num_y));
+				frac.add(den, (den_x, den_y));
+				frac
+			}
+			Node::Sqrt(inner) => {
+				let inner = Formula::node(font, inner);
+				let top = inner.top.min(-BASELINE - GAP) - GAP;
+				let bottom = inner.bottom.max(BASELINE) + 1.0;
+				let middle = (top + bottom) / 2.0;
+				let end = 10.0 + inner.width + 1.0;
+				
+				let mut sqrt = Formula {
+					strokes: vec![vec![
+						(0.0, middle + 1.0), (2.0, middle),
+						(5.0, bottom), (9.0, top),
+						(end, top),
+					]],
+					width: end + 1.0,
+					top, bottom,
+				};
+				sqrt.add(inner, (10.0, 0.0));
+				sqrt
+			}
+			Node::Scripts { base, sup, sub } => {
+				let limits = matches!(**base, Node::Big('∑' | '∏'));
+				let base = Formula::node(font, base);
+				let sup = sup.as_ref().map(|sup| Formula::node(font, sup).scaled(SCRIPT_SIZE));
+				let sub = sub.as_ref().map(|sub| Formula::node(font, sub).scaled(SCRIPT_SIZE));
+				
+				if limits {
+					let width = [Some(&base), sup.as_ref(), sub.as_ref()].iter()
+						.flatten()
+						.map(|f| f.width)
+						.fold(0.0, f64::max);
+					let (base_top, base_bottom) = (base.top, base.bottom);
+					
+					let mut out = Formula { width, ..Formula::default() };
+					let base_x = (width - base.width) / 2.0;
+					out.add(base, (base_x, 0.0));
+					if let Some(sup) = sup {
+						let at = ((width - sup.width) / 2.0, base_top - GAP - sup.bottom);
+						out.add(sup, at);
+					}
+					if let Some(sub) = sub {
+						let at = ((width - sub.width) / 2.0, base_bottom + GAP - sub.top);
+						out.add(sub, at);
+					}
+					return out;
+				}
+				
+				// same as `markup`, but pushed out of the way of tall things
+				let script_baseline = BASELINE * SCRIPT_SIZE;
+				let sup_y = (BASELINE - SUPERSCRIPT_RISE).min(base.top + 12.0) - script_baseline;
+				let sub_y = (BASELINE + SUBSCRIPT_DROP).max(base.bottom + SUBSCRIPT_DROP) - script_baseline;
+				
+				let scripts_width = [sup.as_ref(), sub.as_ref()].iter()
+					.flatten()
+					.map(|f| f.width)
+					.fold(0.0, f64::max);
+				let base_width = base.width;
+				
+				let mut out = Formula { width: base_width + scripts_width, ..Formula::default() };
+				out.add(base, (0.0, 0.0));
+				if let Some(sup) = sup { out.add(sup, (base_width, sup_y)); }
+				if let Some(sub) = sub { out.add(sub, (base_width, sub_y)); }
+				out
+			}
+		}
+	}
+}
+
+/// Lay out `tex`, then put the strokes through `t`. The sizes in the
+/// [`Formula`] are from before `t`, in font units.
+///
+/// Fails with [`HersheyError::InvalidMarkup`] and the byte offset of the
+/// problem for unknown commands, unmatched braces, and doubled-up scripts
+/// like `x^2^3`. Characters the font doesn't have are left out.
+pub fn layout_math<F: Glyphs + ?Sized>(font: &F, tex: &str, t: &Transform) -> Result<Formula, HersheyError> {
+	let mut parser = Parser { chars: tex.char_indices().peekable(), len: tex.len() };
+	let mut formula = Formula::node(font, &parser.row(None)?);
+	
+	for stroke in &mut formula.strokes {
+		for p in stroke.iter_mut() {
+			*p = t.apply(*p);
+		}
+	}
+	
+	Ok(formula)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	use crate::stack::FontStack;
+	
+	fn parse(tex: &str) -> Result<Node, HersheyError> {
+		Parser { chars: tex.char_indices().peekable(), len: tex.len() }.row(None)
+	}
+	
+	#[test]
+	fn parses() {
+		use Node::*;
+		
+		assert_eq!(parse("x^2"), Ok(Row(vec![
+			Scripts { base: Box::new(Char('x')), sup: Some(Box::new(Char('2'))), sub: None },
+		])));
+		assert_eq!(parse("x_{i}^{2}"), parse("x^{2}_{i}"));
+		assert_eq!(parse("x_i^2"), parse("x^2_i"));
+		assert_eq!(parse("\\frac 1 {\\alpha + b}"), Ok(Row(vec![
+			Frac(Box::new(Char('1')), Box::new(Row(vec![Char('α'), Op('+'), Char('b')]))),
+		])));
+		assert_eq!(parse("a \\le \\infty"), Ok(Row(vec![Char('a'), Op('≤'), Char('∞')])));
+		
+		assert_eq!(parse("x^2^3"), Err(HersheyError::InvalidMarkup(3)));
+		assert_eq!(parse("\\sqrt{x"), Err(HersheyError::InvalidMarkup(5)));
+		assert_eq!(parse("x}"), Err(HersheyError::InvalidMarkup(1)));
+		assert_eq!(parse("a + \\bogus"), Err(HersheyError::InvalidMarkup(4)));
+		assert_eq!(parse("\\frac{1}"), Err(HersheyError::InvalidMarkup(8)));
+	}
+	
+	#[test]
+	fn lays_out() {
+		let (futural, greek, mathlow) = (test_font("futural").with_builtin_unicode(), test_font("greek").with_builtin_unicode(), test_font("mathlow").with_builtin_unicode());
+		let font = FontStack::new(&futural).then(&greek).then(&mathlow);
+		let id = Transform::IDENTITY;
+		
+		let x = futural.glyph_for('x').unwrap();
+		let two = futural.glyph_for('2').unwrap();
+		let squared = layout_math(&font, "x^2", &id).unwrap();
+		assert_eq!(squared.strokes.len(), x.strokes().len() + two.strokes().len());
+		assert_eq!(squared.width, (x.advance() as f64) + two.advance() as f64 * SCRIPT_SIZE);
+		
+		// the bar's as wide as the fraction, with the numerator over it and
+		// the denominator under
+		let half = layout_math(&font, "\\frac{1}{2}", &id).unwrap();
+		assert_eq!(half.strokes[0], vec![(0.0, 0.0), (half.width, 0.0)]);
+		assert!(half.strokes[1..].iter().flatten().all(|&(_, y)| y != 0.0));
+		assert!(half.top < -BASELINE - GAP && half.bottom > BASELINE + GAP);
+		
+		// the radical goes over the top of what's inside
+		let root = layout_math(&font, "\\sqrt{x}", &id).unwrap();
+		let radical = &root.strokes[0];
+		assert_eq!(radical.last().unwrap().1, root.top);
+		assert!(root.strokes[1..].iter().flatten().all(|&(x, y)| y > root.top && x > 9.0 && x < radical.last().unwrap().0));
+		
+		// limits go over and under the sum, and are centred on it
+		let sum = layout_math(&font, "\\sum_{i=0}^{n} \\alpha_i", &id).unwrap();
+		let plain_sum = layout_math(&font, "\\sum", &id).unwrap();
+		assert!(sum.top < plain_sum.top && sum.bottom > plain_sum.bottom);
+		assert!(sum.width > plain_sum.width);
+		
+		let moved = layout_math(&font, "x^2", &Transform::translate(1.0, 2.0)).unwrap();
+		assert_eq!(moved.strokes[0][0], (squared.strokes[0][0].0 + 1.0, squared.strokes[0][0].1 + 2.0));
+	}
+}
@@ -0,0 +1,184 @@
+//! Monospaced layout, for code listings and tables of numbers where the
+//! columns have to line up. Every character (or just every digit, for
+//! tabular figures) gets a cell the same width, and sits in the middle of it.
+
+use crate::HersheyChar;
+use crate::layout::{Glyphs, PlacedChar, LINE_HEIGHT};
+use crate::transform::Transform;
+
+/// What gets centred in the cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CentreOn {
+	/// The middle of what the glyph actually draws. Looks the most even, but
+	/// things like `(` and `)` end up in the middle instead of hugging their
+	/// contents. Glyphs that don't draw anything use their box.
+	Ink,
+	
+	/// The middle of the glyph's `left_hand`..`right_hand` box, so any
+	/// sidebearings the font designer put in are kept.
+	Box,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Monospace {
+	/// How far each fixed-width character moves the pen, in font units.
+	pub cell: f64,
+	
+	pub centre_on: CentreOn,
+	
+	/// Only make `0`-`9` fixed width, and leave everything else as it is.
+	pub digits_only: bool,
+}
+
+impl Monospace {
+	/// Every character in a `cell` units wide, centred on its ink.
+	pub fn new(cell: f64) -> Self {
+		Monospace { cell, centre_on: CentreOn::Ink, digits_only: false }
+	}
+	
+	/// Cells as wide as the widest printable ASCII character in `font`.
+	pub fn auto<F: Glyphs + ?Sized>(font: &F) -> Self {
+		Self::new(widest(font, ' '..='~'))
+	}
+	
+	/// Tabular figures: digits get cells as wide as the widest one, and
+	/// everything else is laid out normally.
+	pub fn tabular<F: Glyphs + ?Sized>(font: &F) -> Self {
+		Monospace { digits_only: true, ..Self::new(widest(font, '0'..='9')) }
+	}
+	
+	pub fn centred_on(self, centre_on: CentreOn) -> Self {
+		Monospace { centre_on, ..self }
+	}
+	
+	fn fixed(&self, c: char) -> bool {
+		!self.digits_only || c.is_ascii_digit()
+	}
+}
+
+/// The biggest advance out of `chars`, after scaling. 0 if the font has none of them.
+pub fn widest<F: Glyphs + ?Sized>(font: &F, chars: impl IntoIterator<Item = char>) -> f64 {
+	chars.into_iter()
+		.filter_map(|c| font.sized_glyph(c))
+		.map(|(chr, scale)| chr.advance() as f64 * scale)
+		.fold(0.0, f64::max)
+}
+
+/// Where the middle of `chr` is, in its own coordinates.
+fn middle(chr: &HersheyChar, centre_on: CentreOn) -> f64 {
+	let box_middle = (chr.left_hand as f64 + chr.right_hand as f64) / 2.0;
+	if centre_on == CentreOn::Box { return box_middle; }
+	
+	let mut xs = chr.vertex_data.iter().flatten().map(|&(x, _)| x as f64);
+	match xs.next() {
+		Some(first) => {
+			let (x0, x1) = xs.fold((first, first), |(x0, x1), x| (x0.min(x), x1.max(x)));
+			(x0 + x1) / 2.0
+		}
+		None => box_middle,
+	}
+}
+
+/// Like [`layout_str`](crate::layout::layout_str), but characters go in
+/// fixed-width cells as `mono` says. Glyphs wider than the cell stick out
+/// both sides rather than getting squashed.
+pub fn layout_str_mono<'a, F: Glyphs + ?Sized>(font: &'a F, text: &str, mono: &Monospace, t: &Transform) -> Vec<PlacedChar<'a>> {
+	let mut placed = Vec::new();
+	let mut line = 0;
+	let mut pen_x = 0.0;
+	
+	for (index, ch) in text.chars().enumerate() {
+		if ch == '\n' {
+			line += 1;
+			pen_x = 0.0;
+			continue;
+		}
+		
+		if let Some((chr, scale)) = font.sized_glyph(ch) {
+			let y = line as f64 * LINE_HEIGHT;
+			let (origin, advance) = if mono.fixed(ch) {
+				let origin = Transform::translate(-middle(chr, mono.centre_on), 0.0)
+					.then(&Transform::uniform_scale(scale))
+					.then(&Transform::translate(pen_x + mono.cell / 2.0, y));
+				(origin, mono.cell)
+			} else {
+				let origin = Transform::translate(-chr.left_hand as f64, 0.0)
+					.then(&Transform::uniform_scale(scale))
+					.then(&Transform::translate(pen_x, y));
+				(origin, chr.advance() as f64 * scale)
+			};
+			
+			placed.push(PlacedChar {
+				chr,
+				transform: origin.then(t),
+				line, index,
+			});
+			pen_x += advance;
+		}
+	}
+	
+	placed
+}
+
+/// How far the pen moves for `line` with [`layout_str_mono`], in font units.
+/// Like [`line_width`](crate::layout::line_width), split lines first.
+pub fn line_width_mono<F: Glyphs + ?Sized>(font: &F, line: &str, mono: &Monospace) -> f64 {
+	line.chars()
+		.filter_map(|ch| Some((ch, font.sized_glyph(ch)?)))
+		.map(|(ch, (chr, scale))| if mono.fixed(ch) { mono.cell } else { chr.advance() as f64 * scale })
+		.sum()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	use crate::layout::{layout_str, line_width};
+	
+	#[test]
+	fn fixed_cells() {
+		let font = test_font("futural");
+		let mono = Monospace::auto(&font);
+		assert_eq!(mono.cell, widest(&font, ' '..='~'));
+		assert!(mono.cell >= font.glyph_for('W').unwrap().advance() as f64);
+		
+		// columns line up however wide the letters are
+		let placed = layout_str_mono(&font, "il\nWM", &mono, &Transform::IDENTITY);
+		assert_eq!(line_width_mono(&font, "il", &mono), 2.0 * mono.cell);
+		assert_eq!(line_width_mono(&font, "WM", &mono), 2.0 * mono.cell);
+		
+		// the l's ink is a single line, right in the middle of its cell
+		let l = &placed[1];
+		assert_eq!(l.strokes()[0][0].0, mono.cell * 1.5);
+		
+		// centring on the box instead goes by left_hand and right_hand
+		let boxed = layout_str_mono(&font, "l", &mono.centred_on(CentreOn::Box), &Transform::IDENTITY);
+		let chr = boxed[0].chr;
+		let (left, _) = boxed[0].transform.apply((chr.left_hand as f64, 0.0));
+		let (right, _) = boxed[0].transform.apply((chr.right_hand as f64, 0.0));
+		assert_eq!((left + right) / 2.0, mono.cell / 2.0);
+		
+		assert_eq!(placed[3].line, 1);
+		assert_eq!(placed[3].index, 4);
+	}
+	
+	#[test]
+	fn tabular_figures() {
+		let font = test_font("futural");
+		let mono = Monospace::tabular(&font);
+		assert!(mono.digits_only);
+		assert_eq!(mono.cell, widest(&font, '0'..='9'));
+		
+		// the 1 gets as much room as the 0, and the rest is left alone
+		assert_eq!(line_width_mono(&font, "1.5", &mono), line_width_mono(&font, "0.5", &mono));
+		assert_eq!(line_width_mono(&font, "ab", &mono), line_width(&font, "ab"));
+		
+		let placed = layout_str_mono(&font, "a1", &mono, &Transform::IDENTITY);
+		assert_eq!(placed[0], layout_str(&font, "a", &Transform::IDENTITY)[0]);
+		let a_width = line_width(&font, "a");
+		let one = &placed[1];
+		let (x0, x1) = one.strokes().iter().flatten()
+			.fold((f64::INFINITY, f64::NEG_INFINITY), |(x0, x1), &(x, _)| (x0.min(x), x1.max(x)));
+		assert!(((x0 + x1) / 2.0 - (a_width + mono.cell / 2.0)).abs() < 1e-9);
+	}
+}
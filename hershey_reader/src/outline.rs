@@ -0,0 +1,524 @@
+//! Turning centre-line strokes into filled outlines, like a pen of some
+//! width would draw them.
+//!
+//! Every segment, cap and join becomes its own little convex polygon, and
+//! then they all get unioned: edges are split wherever they cross, and only
+//! the bits with nothing else on their outside are kept.
+
+use std::collections::HashMap;
+use core::f64::consts::PI;
+
+use crate::{HersheyChar, Point, Stroke};
+
+/// A closed loop of points. The last point isn't repeated.
+///
+/// Outer contours have positive area (by the shoelace formula, so clockwise
+/// on screen with +y down), and holes have negative area.
+pub type Contour = Vec<Point>;
+
+/// What the ends of strokes look like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Cap {
+	/// Stops dead at the end point.
+	Butt,
+	/// Half a circle past the end point.
+	#[default]
+	Round,
+	/// Half a pen width past the end point, squared off.
+	Square,
+}
+
+/// What corners look like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Join {
+	#[default]
+	Round,
+	/// Pointy, until it gets longer than [`Pen::miter_limit`]; then it's a bevel.
+	Miter,
+	Bevel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pen {
+	pub width: f64,
+	pub cap: Cap,
+	pub join: Join,
+	
+	/// Longest a miter can get, in half pen widths. Same as SVG's `stroke-miterlimit`.
+	pub miter_limit: f64,
+}
+
+impl Default for Pen {
+	fn default() -> Self {
+		Pen {
+			width: 1.0,
+			cap: Cap::Round,
+			join: Join::Round,
+			miter_limit: 4.0,
+		}
+	}
+}
+
+/// Arcs get one point per this many radians.
+const ARC_STEP: f64 = PI / 16.0;
+
+fn add(a: Point, b: Point) -> Point { (a.0 + b.0, a.1 + b.1) }
+fn sub(a: Point, b: Point) -> Point { (a.0 - b.0, a.1 - b.1) }
+fn mul(a: Point, s: f64) -> Point { (a.0 * s, a.1 * s) }
+fn dot(a: Point, b: Point) -> f64 { a.0 * b.0 + a.1 * b.1 }
+fn cross(a: Point, b: Point) -> f64 { a.0 * b.1 - a.1 * b.0 }
+
+fn unit(a: Point) -> Point {
+	let l = a.0.hypot(a.1);
+	(a.0 / l, a.1 / l)
+}
+
+/// Shoelace formula. See [`Contour`] for which way is positive.
+pub fn signed_area(poly: &[Point]) -> f64 {
+	let n = poly.len();
+	(0..n).map(|i| cross(poly[i], poly[(i + 1) % n])).sum::<f64>() / 2.0
+}
+
+/// Outline every stroke of `chr` with `pen`.
+pub fn outline_char(chr: &HersheyChar, pen: &Pen) -> Vec<Contour> {
+	outline_strokes(&chr.strokes(), pen)
+}
+
+/// Outline `strokes` with `pen`, giving non-overlapping, non-self-intersecting
+/// contours. Overlapping strokes get merged together.
+pub fn outline_strokes(strokes: &[Stroke], pen: &Pen) -> Vec<Contour> {
+	let polys = strokes.iter()
+		.flat_map(|s| pieces(s, pen))
+		.filter(|p| signed_area(p) > 1e-12)
+		.collect::<Vec<_>>();
+	
+	union(&polys)
+}
+
+/// Points around `center` from `from` to `to` (both offsets of length `r`),
+/// going the short way. The ends are exactly `center + from` and `center + to`.
+fn arc(center: Point, r: f64, from: Point, to: Point) -> Vec<Point> {
+	let a1 = from.1.atan2(from.0);
+	let mut sweep = to.1.atan2(to.0) - a1;
+	while sweep > PI { sweep -= 2.0 * PI; }
+	while sweep < -PI { sweep += 2.0 * PI; }
+	
+	let steps = (sweep.abs() / ARC_STEP).ceil().max(1.0) as usize;
+	
+	let mut out = vec![add(center, from)];
+	for i in 1..steps {
+		let a = a1 + sweep * i as f64 / steps as f64;
+		out.push((center.0 + r * a.cos(), center.1 + r * a.sin()));
+	}
+	out.push(add(center, to));
+	out
+}
+
+fn ccw(mut poly: Vec<Point>) -> Vec<Point> {
+	if signed_area(&poly) < 0.0 { poly.reverse(); }
+	poly
+}
+
+/// The convex pieces that make up one stroke.
+fn pieces(stroke: &[Point], pen: &Pen) -> Vec<Vec<Point>> {
+	let h = pen.width / 2.0;
+	let mut points = stroke.to_vec();
+	points.dedup();
+	
+	let mut polys = Vec::new();
+	
+	if points.len() == 1 {
+		let p = points[0];
+		match pen.cap {
+			Cap::Round => {
+				let steps = (2.0 * PI / ARC_STEP).ceil() as usize;
+				polys.push(ccw((0..steps)
+					.map(|i| 2.0 * PI * i as f64 / steps as f64)
+					.map(|a| (p.0 + h * a.cos(), p.1 + h * a.sin()))
+					.collect()));
+			},
+			Cap::Square => polys.push(ccw(vec![
+				add(p, (-h, -h)), add(p, (h, -h)), add(p, (h, h)), add(p, (-h, h)),
+			])),
+			Cap::Butt => {},
+		}
+		return polys;
+	}
+	
+	let closed = points.len() > 3 && points.first() == points.last();
+	let last = points.len() - 2;
+	
+	for (i, pair) in points.windows(2).enumerate() {
+		let (a, b) = (pair[0], pair[1]);
+		let d = unit(sub(b, a));
+		let n = mul((-d.1, d.0), h);
+		
+		let square = pen.cap == Cap::Square && !closed;
+		let a = if square && i == 0 { sub(a, mul(d, h)) } else { a };
+		let b = if square && i == last { add(b, mul(d, h)) } else { b };
+		
+		polys.push(ccw(vec![add(a, n), sub(a, n), sub(b, n), add(b, n)]));
+	}
+	
+	if pen.cap == Cap::Round && !closed {
+		for (p, toward) in [(points[0], points[1]), (points[last + 1], points[last])] {
+			let d = unit(sub(toward, p));
+			let n = mul((-d.1, d.0), h);
+			
+			// half a circle from +n round to -n, bulging away from the stroke
+			let steps = (PI / ARC_STEP).ceil() as usize;
+			let mut cap = vec![add(p, n)];
+			for i in 1..steps {
+				let a = PI * i as f64 / steps as f64;
+				cap.push(sub(add(p, mul(n, a.cos())), mul(d, h * a.sin())));
+			}
+			cap.push(sub(p, n));
+			polys.push(ccw(cap));
+		}
+	}
+	
+	let mut corners: Vec<(Point, Point, Point)> = (1..points.len() - 1)
+		.map(|i| (points[i - 1], points[i], points[i + 1]))
+		.collect();
+	if closed {
+		corners.push((points[points.len() - 2], points[0], points[1]));
+	}
+	
+	for (prev, v, next) in corners {
+		let (d1, d2) = (unit(sub(v, prev)), unit(sub(next, v)));
+		let turn = cross(d1, d2);
+		if turn == 0.0 && dot(d1, d2) > 0.0 { continue; }
+		
+		// offsets on the outside of the turn
+		let side = if turn > 0.0 { -h } else { h };
+		let o1 = mul((-d1.1, d1.0), side);
+		let o2 = mul((-d2.1, d2.0), side);
+		
+		match pen.join {
+			Join::Round => {
+				let mut fan = vec![v];
+				fan.extend(arc(v, h, o1, o2));
+				polys.push(ccw(fan));
+			},
+			Join::Miter | Join::Bevel => {
+				let cos = dot(d1, d2);
+				let ratio = (2.0 / (1.0 + cos)).sqrt();
+				
+				if pen.join == Join::Miter && cos > -1.0 && ratio <= pen.miter_limit {
+					let tip = add(v, mul(add(o1, o2), 1.0 / (1.0 + cos)));
+					polys.push(ccw(vec![v, add(v, o1), tip, add(v, o2)]));
+				} else {
+					polys.push(ccw(vec![v, add(v, o1), add(v, o2)]));
+				}
+			},
+		}
+	}
+	
+	polys
+}
+
+const EPS: f64 = 1e-9;
+
+/// Strictly inside a convex, positive-area polygon.
+fn inside(poly: &[Point], p: Point) -> bool {
+	let n = poly.len();
+	(0..n).all(|i| cross(sub(poly[(i + 1) % n], poly[i]), sub(p, poly[i])) > EPS)
+}
+
+fn bounds(poly: &[Point]) -> (Point, Point) {
+	poly.iter().fold(
+		((f64::INFINITY, f64::INFINITY), (f64::NEG_INFINITY, f64::NEG_INFINITY)),
+		|(lo, hi), &p| ((lo.0.min(p.0), lo.1.min(p.1)), (hi.0.max(p.0), hi.1.max(p.1))),
+	)
+}
+
+/// Where along `a`–`b` the point `p` is, if it's on there.
+fn on_segment(a: Point, b: Point, p: Point) -> Option<f64> {
+	let ab = sub(b, a);
+	let len2 = dot(ab, ab);
+	let t = dot(sub(p, a), ab) / len2;
+	let off = cross(ab, sub(p, a)).abs() / len2.sqrt();
+	(t > EPS && t < 1.0 - EPS && off < EPS).then_some(t)
+}
+
+/// Where two segments properly cross, as (t along the first, u along the second, point).
+fn crossing(a: Point, b: Point, c: Point, d: Point) -> Option<(f64, f64, Point)> {
+	let (r, s) = (sub(b, a), sub(d, c));
+	let denom = cross(r, s);
+	if denom.abs() < EPS { return None; }
+	
+	let t = cross(sub(c, a), s) / denom;
+	let u = cross(sub(c, a), r) / denom;
+	(t > EPS && t < 1.0 - EPS && u > EPS && u < 1.0 - EPS)
+		.then(|| (t, u, add(a, mul(r, t))))
+}
+
+fn key(p: Point) -> (i64, i64) {
+	((p.0 * 1e6).round() as i64, (p.1 * 1e6).round() as i64)
+}
+
+fn union(polys: &[Vec<Point>]) -> Vec<Contour> {
+	let edge = |i: usize, e: usize| {
+		let p = &polys[i];
+		(p[e], p[(e + 1) % p.len()])
+	};
+	
+	// Extra points to cut each edge at, as (t, point).
+	let mut cuts: Vec<Vec<Vec<(f64, Point)>>> = polys.iter()
+		.map(|p| vec![Vec::new(); p.len()])
+		.collect();
+	let boxes: Vec<_> = polys.iter().map(|p| bounds(p)).collect();
+	
+	for i in 0..polys.len() {
+		for j in i + 1..polys.len() {
+			let ((lo1, hi1), (lo2, hi2)) = (boxes[i], boxes[j]);
+			if lo1.0 > hi2.0 + EPS || lo2.0 > hi1.0 + EPS || lo1.1 > hi2.1 + EPS || lo2.1 > hi1.1 + EPS {
+				continue;
+			}
+			
+			for ei in 0..polys[i].len() {
+				let (a, b) = edge(i, ei);
+				for ej in 0..polys[j].len() {
+					let (c, d) = edge(j, ej);
+					
+					if let Some((t, u, p)) = crossing(a, b, c, d) {
+						cuts[i][ei].push((t, p));
+						cuts[j][ej].push((u, p));
+					}
+					if let Some(t) = on_segment(a, b, c) { cuts[i][ei].push((t, c)); }
+					if let Some(u) = on_segment(c, d, a) { cuts[j][ej].push((u, a)); }
+				}
+			}
+		}
+	}
+	
+	// Keep the bits of edges that are on the outside of everything.
+	let mut fragments = Vec::new();
+	for (i, poly_cuts) in cuts.into_iter().enumerate() {
+		for (e, mut edge_cuts) in poly_cuts.into_iter().enumerate() {
+			let (a, b) = edge(i, e);
+			let mut points = vec![(0.0, a)];
+			points.append(&mut edge_cuts);
+			points.push((1.0, b));
+			points.sort_by(|x, y| x.0.total_cmp(&y.0));
+			
+			let out = unit((b.1 - a.1, a.0 - b.0));
+			for pair in points.windows(2) {
+				let (p, q) = (pair[0].1, pair[1].1);
+				if key(p) == key(q) { continue; }
+				
+				let mid = mul(add(p, q), 0.5);
+				let outside = add(mid, mul(out, 1e-6));
+				let inward = sub(mid, mul(out, 1e-6));
+				
+				let covered = polys.iter().enumerate().any(|(k, other)| k != i && (
+					inside(other, mid)
+					|| inside(other, outside)
+					// the same edge twice: keep the first one
+					|| (k < i && inside(other, inward))
+				));
+				
+				if !covered {
+					fragments.push((p, q));
+				}
+			}
+		}
+	}
+	
+	chain(&fragments)
+}
+
+/// Join up directed fragments into loops.
+fn chain(fragments: &[(Point, Point)]) -> Vec<Contour> {
+	let mut starting_at: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+	for (i, (a, _)) in fragments.iter().enumerate() {
+		starting_at.entry(key(*a)).or_default().push(i);
+	}
+	
+	let mut used = vec![false; fragments.len()];
+	let mut contours = Vec::new();
+	
+	for first in 0..fragments.len() {
+		if used[first] { continue; }
+		used[first] = true;
+		
+		let start = key(fragments[first].0);
+		let mut contour = vec![fragments[first].0];
+		let mut cur = first;
+		
+		while key(fragments[cur].1) != start {
+			let (a, b) = fragments[cur];
+			let dir = sub(b, a);
+			
+			// Where loops touch, turn as hard right as possible so they stay separate.
+			let next = starting_at.get(&key(b))
+				.into_iter()
+				.flatten()
+				.filter(|&&f| !used[f])
+				.min_by(|&&f, &&g| {
+					let turn = |f: usize| {
+						let d = sub(fragments[f].1, fragments[f].0);
+						cross(dir, d).atan2(dot(dir, d))
+					};
+					turn(f).total_cmp(&turn(g))
+				});
+			
+			match next {
+				Some(&f) => {
+					used[f] = true;
+					contour.push(fragments[f].0);
+					cur = f;
+				},
+				None => break,
+			}
+		}
+		
+		let contour = tidy(contour);
+		if contour.len() >= 3 && signed_area(&contour).abs() > 1e-9 {
+			contours.push(contour);
+		}
+	}
+	
+	contours
+}
+
+/// Drop points that sit in the middle of a straight line.
+fn tidy(mut contour: Contour) -> Contour {
+	let mut i = 0;
+	while contour.len() >= 3 && i < contour.len() {
+		let n = contour.len();
+		let (prev, cur, next) = (contour[(i + n - 1) % n], contour[i], contour[(i + 1) % n]);
+		let (d1, d2) = (sub(cur, prev), sub(next, cur));
+		
+		let straight = cross(d1, d2).abs() <= 1e-9 * (dot(d1, d1) + dot(d2, d2)).sqrt().max(1.0)
+			&& dot(d1, d2) >= 0.0;
+		if straight || key(prev) == key(cur) {
+			contour.remove(i);
+			i = i.saturating_sub(1);
+		} else {
+			i += 1;
+		}
+	}
+	contour
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn pen(cap: Cap, join: Join) -> Pen {
+		Pen { width: 2.0, cap, join, miter_limit: 4.0 }
+	}
+	
+	fn total_area(contours: &[Contour]) -> f64 {
+		contours.iter().map(|c| signed_area(c)).sum()
+	}
+	
+	/// No two edges anywhere cross each other.
+	fn is_clean(contours: &[Contour]) -> bool {
+		let edges: Vec<(Point, Point)> = contours.iter()
+			.flat_map(|c| (0..c.len()).map(move |i| (c[i], c[(i + 1) % c.len()])))
+			.collect();
+		
+		edges.iter().enumerate().all(|(i, &(a, b))| {
+			edges[i + 1..].iter().all(|&(c, d)| crossing(a, b, c, d).is_none())
+		})
+	}
+	
+	#[test]
+	fn butt_line_is_a_rectangle() {
+		let contours = outline_strokes(&[vec![(0.0, 0.0), (10.0, 0.0)]], &pen(Cap::Butt, Join::Miter));
+		
+		assert_eq!(contours.len(), 1);
+		assert_eq!(contours[0].len(), 4);
+		assert!((total_area(&contours) - 20.0).abs() < 1e-9);
+	}
+	
+	#[test]
+	fn square_caps_add_half_a_width() {
+		let contours = outline_strokes(&[vec![(0.0, 0.0), (10.0, 0.0)]], &pen(Cap::Square, Join::Miter));
+		assert!((total_area(&contours) - 24.0).abs() < 1e-9);
+	}
+	
+	#[test]
+	fn round_caps_are_round() {
+		let contours = outline_strokes(&[vec![(0.0, 0.0), (10.0, 0.0)]], &pen(Cap::Round, Join::Round));
+		
+		assert_eq!(contours.len(), 1);
+		assert!(is_clean(&contours));
+		let area = total_area(&contours);
+		assert!(area < 20.0 + PI && area > 20.0 + PI * 0.98);
+	}
+	
+	#[test]
+	fn dots() {
+		let dot = [vec![(3.0, 3.0)]];
+		
+		let round = outline_strokes(&dot, &pen(Cap::Round, Join::Round));
+		assert!((total_area(&round) - PI).abs() < 0.05);
+		
+		let square = outline_strokes(&dot, &pen(Cap::Square, Join::Round));
+		assert!((total_area(&square) - 4.0).abs() < 1e-9);
+		
+		assert!(outline_strokes(&dot, &pen(Cap::Butt, Join::Round)).is_empty());
+	}
+	
+	#[test]
+	fn corners() {
+		let ell = [vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]];
+		
+		let miter = outline_strokes(&ell, &pen(Cap::Butt, Join::Miter));
+		assert_eq!(miter.len(), 1);
+		assert_eq!(miter[0].len(), 6);
+		// two 10x2 arms, overlapping in a 1x1 square, plus the 1x1 miter corner
+		assert!((total_area(&miter) - 40.0).abs() < 1e-9);
+		
+		let bevel = outline_strokes(&ell, &pen(Cap::Butt, Join::Bevel));
+		assert_eq!(bevel[0].len(), 7);
+		assert!((total_area(&bevel) - 39.5).abs() < 1e-9);
+		
+		let round = outline_strokes(&ell, &pen(Cap::Butt, Join::Round));
+		let area = total_area(&round);
+		assert!(area > 39.5 && area < 39.0 + PI / 4.0 + 1e-9);
+	}
+	
+	#[test]
+	fn crossing_strokes_merge() {
+		let cross = [
+			vec![(-5.0, 0.0), (5.0, 0.0)],
+			vec![(0.0, -5.0), (0.0, 5.0)],
+		];
+		let contours = outline_strokes(&cross, &pen(Cap::Butt, Join::Miter));
+		
+		assert_eq!(contours.len(), 1);
+		assert_eq!(contours[0].len(), 12);
+		assert!((total_area(&contours) - (20.0 + 20.0 - 4.0)).abs() < 1e-9);
+	}
+	
+	#[test]
+	fn loops_have_holes() {
+		let square = [vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 0.0)]];
+		let contours = outline_strokes(&square, &pen(Cap::Butt, Join::Miter));
+		
+		assert_eq!(contours.len(), 2);
+		assert!(contours.iter().any(|c| signed_area(c) < 0.0));
+		assert!((total_area(&contours) - (12.0 * 12.0 - 8.0 * 8.0)).abs() < 1e-9);
+	}
+	
+	#[test]
+	fn real_glyphs_come_out_clean() {
+		let font = crate::test_font("futural");
+		
+		for c in "AB8@&".chars() {
+			let chr = font.glyph_for(c).unwrap();
+			let contours = outline_char(chr, &Pen::default());
+			
+			assert!(!contours.is_empty());
+			assert!(total_area(&contours) > 0.0);
+			assert!(is_clean(&contours), "{}", c);
+		}
+	}
+}
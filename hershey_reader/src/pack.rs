@@ -0,0 +1,592 @@
+//! A binary container for one or more fonts, that can be read in place
+//! without parsing anything up front. [`Pack::new`] takes any byte slice,
+//! so a memory-mapped file works just as well as one that's been read in.
+//!
+//! Everything's little-endian, and every section starts on a 4-byte boundary:
+//!
+//! - a 24-byte header: the magic `HRSHYPK\0`, a `u16` version (`1`), a `u16`
+//!   of flags (`0`), the `u32` font count, the `u32` length of the whole
+//!   file, and a `u32` CRC-32 of everything after the header.
+//! - a directory of 52-byte entries, one per font, each 13 `u32`s: where
+//!   its name is and how long it is, its flags (`1` means ASCII order), then
+//!   an offset and a count for each of its glyphs, strokes, points, char
+//!   map, and kerning pairs.
+//! - each font's sections, which the directory points into:
+//!   - glyphs, 16 bytes each: a `u32` id, `i8` left and right hand, two
+//!     bytes of padding, and the `u32` index and count of its strokes.
+//!   - strokes, one `u32` each: the index of the point after the stroke's
+//!     last one. It starts where the one before it ended.
+//!   - points, an `(i8, i8)` each, +y down like in the .jhf.
+//!   - the char map, `(u32, u32)` pairs of codepoint and glyph index,
+//!     sorted by codepoint.
+//!   - kerning, `(u32, u32, i32)`: left codepoint, right codepoint, and how
+//!     much to add to the space between them, sorted by left then right.
+
+use std::convert::TryFrom;
+
+use crate::{HersheyChar, HersheyError, HersheyFont, Point, Stroke};
+
+const MAGIC: &[u8; 8] = b"HRSHYPK\0";
+const VERSION: u16 = 1;
+
+const HEADER_SIZE: usize = 24;
+const ENTRY_SIZE: usize = 13 * 4;
+const GLYPH_SIZE: usize = 16;
+
+/// Set in a font's flags if it has no [`unicode`](HersheyFont::unicode) table.
+const ASCII_ORDER: u32 = 1;
+
+const CRC_TABLE: [u32; 256] = {
+	let mut table = [0; 256];
+	let mut i = 0;
+	while i < 256 {
+		let mut c = i as u32;
+		let mut k = 0;
+		while k < 8 {
+			c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+			k += 1;
+		}
+		table[i] = c;
+		i += 1;
+	}
+	table
+};
+
+/// The usual CRC-32, same as zip and png.
+fn crc32(data: &[u8]) -> u32 {
+	!data.iter().fold(!0, |c, &b| CRC_TABLE[((c ^ b as u32) & 0xFF) as usize] ^ (c >> 8))
+}
+
+fn u32_at(data: &[u8], at: usize) -> u32 {
+	u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+fn pad(out: &mut Vec<u8>) {
+	while !out.len().is_multiple_of(4) { out.push(0); }
+}
+
+/// Every count and offset in a pack is a `u32`.
+fn to_u32(v: usize) -> Result<u32, HersheyError> {
+	u32::try_from(v).map_err(|_| HersheyError::OutOfRange)
+}
+
+/// A font to go in a pack, with any kerning it should bring along.
+#[derive(Debug, Clone, Copy)]
+pub struct PackFont<'a> {
+	pub font: &'a HersheyFont,
+	/// `(left, right, adjust)`: add `adjust` to the space between `left` and
+	/// `right` when they're next to each other.
+	pub kerning: &'a [(char, char, i32)],
+}
+
+impl<'a> From<&'a HersheyFont> for PackFont<'a> {
+	fn from(font: &'a HersheyFont) -> Self {
+		PackFont { font, kerning: &[] }
+	}
+}
+
+/// Write `fonts` out as a pack. Fails with [`HersheyError::OutOfRange`] if
+/// a glyph id, or any count or offset, doesn't fit in a `u32`.
+pub fn to_pack(fonts: &[PackFont]) -> Result<Vec<u8>, HersheyError> {
+	let mut out = Vec::new();
+	out.extend_from_slice(MAGIC);
+	out.extend_from_slice(&VERSION.to_le_bytes());
+	out.extend_from_slice(&0u16.to_le_bytes());
+	out.extend_from_slice(&to_u32(fonts.len())?.to_le_bytes());
+	// length and checksum go here once they're known
+	out.resize(HEADER_SIZE + fonts.len() * ENTRY_SIZE, 0);
+	
+	for (n, PackFont { font, kerning }) in fonts.iter().enumerate() {
+		let mut entry = Vec::new();
+		let mut section = |out: &mut Vec<u8>, count: usize| -> Result<(), HersheyError> {
+			pad(out);
+			entry.push(to_u32(out.len())?);
+			entry.push(to_u32(count)?);
+			Ok(())
+		};
+		
+		section(&mut out, font.name.len())?;
+		out.extend_from_slice(font.name.as_bytes());
+		
+		// the same split as the JSON format, so doubled-up pen-ups survive
+		let runs = font.chars.iter()
+			.map(|chr| if chr.vertex_data.is_empty() { Vec::new() } else {
+				chr.vertex_data.split(Option::is_none).collect()
+			})
+			.collect::<Vec<_>>();
+		let stroke_count = runs.iter().map(Vec::len).sum();
+		let point_count = font.chars.iter().map(|chr| chr.vertex_data.iter().flatten().count()).sum();
+		
+		section(&mut out, font.chars.len())?;
+		let mut first_stroke = 0;
+		for (chr, runs) in font.chars.iter().zip(&runs) {
+			out.extend_from_slice(&to_u32(chr.id)?.to_le_bytes());
+			out.extend_from_slice(&[chr.left_hand as u8, chr.right_hand as u8, 0, 0]);
+			out.extend_from_slice(&to_u32(first_stroke)?.to_le_bytes());
+			out.extend_from_slice(&to_u32(runs.len())?.to_le_bytes());
+			first_stroke += runs.len();
+		}
+		
+		section(&mut out, stroke_count)?;
+		let mut end = 0;
+		for run in runs.iter().flatten() {
+			end += run.len();
+			out.extend_from_slice(&to_u32(end)?.to_le_bytes());
+		}
+		
+		section(&mut out, point_count)?;
+		for &(x, y) in font.chars.iter().flat_map(|chr| chr.vertex_data.iter().flatten()) {
+			out.extend_from_slice(&[x as u8, y as u8]);
+		}
+		
+		let mut map = font.char_map();
+		map.sort_unstable();
+		section(&mut out, map.len())?;
+		for (c, i) in map {
+			out.extend_from_slice(&(c as u32).to_le_bytes());
+			out.extend_from_slice(&to_u32(i)?.to_le_bytes());
+		}
+		
+		let mut kerning = kerning.to_vec();
+		kerning.sort_unstable();
+		section(&mut out, kerning.len())?;
+		for (left, right, adjust) in kerning {
+			out.extend_from_slice(&(left as u32).to_le_bytes());
+			out.extend_from_slice(&(right as u32).to_le_bytes());
+			out.extend_from_slice(&adjust.to_le_bytes());
+		}
+		
+		let flags = if font.unicode.is_none() { ASCII_ORDER } else { 0 };
+		entry.insert(2, flags);
+		
+		let at = HEADER_SIZE + n * ENTRY_SIZE;
+		for (i, v) in entry.iter().enumerate() {
+			out[at + i * 4..][..4].copy_from_slice(&v.to_le_bytes());
+		}
+	}
+	
+	pad(&mut out);
+	let len = to_u32(out.len())?;
+	out[16..20].copy_from_slice(&len.to_le_bytes());
+	let crc = crc32(&out[HEADER_SIZE..]);
+	out[20..24].copy_from_slice(&crc.to_le_bytes());
+	Ok(out)
+}
+
+/// A pack that's been checked over and is ready to read from.
+#[derive(Debug, Clone, Copy)]
+pub struct Pack<'a> {
+	data: &'a [u8],
+	fonts: usize,
+}
+
+impl<'a> Pack<'a> {
+	/// Check the header, checksum, and that everything points somewhere it
+	/// should. Nothing gets copied. Fails with [`HersheyError::InvalidPack`]
+	/// if any of it's off.
+	pub fn new(data: &'a [u8]) -> Result<Self, HersheyError> {
+		let bad = HersheyError::InvalidPack;
+		
+		if data.len() < HEADER_SIZE || &data[..8] != MAGIC { return Err(bad); }
+		if u16::from_le_bytes([data[8], data[9]]) != VERSION { return Err(bad); }
+		
+		let fonts = u32_at(data, 12) as usize;
+		let len = u32_at(data, 16) as usize;
+		if len != data.len() || crc32(&data[HEADER_SIZE..]) != u32_at(data, 20) { return Err(bad); }
+		
+		let pack = Pack { data, fonts };
+		if HEADER_SIZE + fonts * ENTRY_SIZE > len { return Err(bad); }
+		
+		for i in 0..fonts {
+			let font = pack.entry(i);
+			
+			let sizes = [1, GLYPH_SIZE, 4, 2, 8, 12];
+			for (s, size) in sizes.iter().enumerate() {
+				let (at, count) = font.section(s);
+				if count.checked_mul(*size).and_then(|n| at.checked_add(n)).is_none_or(|end| end > len) { return Err(bad); }
+			}
+			
+			std::str::from_utf8(font.bytes(0, 1)).map_err(|_| bad.clone())?;
+			
+			let strokes = font.section(2).1;
+			for g in 0..font.len() {
+				let (first, count) = font.glyph_strokes(g);
+				if first + count > strokes { return Err(bad); }
+			}
+			
+			let mut last = 0;
+			for s in 0..strokes {
+				let end = font.stroke_end(s);
+				if end < last || end > font.section(3).1 { return Err(bad); }
+				last = end;
+			}
+			
+			for m in 0..font.section(4).1 {
+				let (c, g) = font.map_entry(m);
+				if char::from_u32(c).is_none() || g as usize >= font.len() { return Err(bad); }
+			}
+			
+			for k in 0..font.section(5).1 {
+				let (l, r, _) = font.kern_entry(k);
+				if char::from_u32(l).is_none() || char::from_u32(r).is_none() { return Err(bad); }
+			}
+		}
+		
+		Ok(pack)
+	}
+	
+	/// How many fonts are in here.
+	pub fn len(&self) -> usize {
+		self.fonts
+	}
+	
+	pub fn is_empty(&self) -> bool {
+		self.fonts == 0
+	}
+	
+	pub fn font(&self, i: usize) -> Option<PackedFont<'a>> {
+		if i < self.fonts { Some(self.entry(i)) } else { None }
+	}
+	
+	pub fn fonts(&self) -> impl Iterator<Item = PackedFont<'a>> + '_ {
+		(0..self.fonts).map(move |i| self.entry(i))
+	}
+	
+	/// The first font called `name`.
+	pub fn find(&self, name: &str) -> Option<PackedFont<'a>> {
+		self.fonts().find(|f| f.name() == name)
+	}
+	
+	fn entry(&self, i: usize) -> PackedFont<'a> {
+		PackedFont { data: self.data, entry: HEADER_SIZE + i * ENTRY_SIZE }
+	}
+}
+
+/// One font in a [`Pack`]. Everything's read straight out of the pack's bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct PackedFont<'a> {
+	data: &'a [u8],
+	entry: usize,
+}
+
+impl<'a> PackedFont<'a> {
+	/// Offset and count of a section: 0 is the name, then glyphs, strokes,
+	/// points, the char map, and kerning.
+	fn section(&self, s: usize) -> (usize, usize) {
+		let at = if s == 0 { 0 } else { 3 + (s - 1) * 2 };
+		let at = self.entry + at * 4;
+		(u32_at(self.data, at) as usize, u32_at(self.data, at + 4) as usize)
+	}
+	
+	fn bytes(&self, s: usize, size: usize) -> &'a [u8] {
+		let (at, count) = self.section(s);
+		&self.data[at..at + count * size]
+	}
+	
+	fn flags(&self) -> u32 {
+		u32_at(self.data, self.entry + 8)
+	}
+	
+	fn glyph_strokes(&self, g: usize) -> (usize, usize) {
+		let at = self.section(1).0 + g * GLYPH_SIZE;
+		(u32_at(self.data, at + 8) as usize, u32_at(self.data, at + 12) as usize)
+	}
+	
+	fn stroke_end(&self, s: usize) -> usize {
+		u32_at(self.data, self.section(2).0 + s * 4) as usize
+	}
+	
+	fn map_entry(&self, m: usize) -> (u32, u32) {
+		let at = self.section(4).0 + m * 8;
+		(u32_at(self.data, at), u32_at(self.data, at + 4))
+	}
+	
+	fn kern_entry(&self, k: usize) -> (u32, u32, i32) {
+		let at = self.section(5).0 + k * 12;
+		(u32_at(self.data, at), u32_at(self.data, at + 4), u32_at(self.data, at + 8) as i32)
+	}
+	
+	pub fn name(&self) -> &'a str {
+		// checked back in `Pack::new`
+		std::str::from_utf8(self.bytes(0, 1)).unwrap()
+	}
+	
+	/// How many glyphs there are.
+	pub fn len(&self) -> usize {
+		self.section(1).1
+	}
+	
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+	
+	pub fn glyph(&self, i: usize) -> Option<PackedGlyph<'a>> {
+		if i >= self.len() { return None; }
+		
+		let at = self.section(1).0 + i * GLYPH_SIZE;
+		let (first, count) = self.glyph_strokes(i);
+		let start = if first == 0 { 0 } else { self.stroke_end(first - 1) };
+		
+		Some(PackedGlyph {
+			id: u32_at(self.data, at) as usize,
+			left_hand: self.data[at + 4] as i8,
+			right_hand: self.data[at + 5] as i8,
+			start,
+			ends: &self.bytes(2, 4)[first * 4..(first + count) * 4],
+			points: self.bytes(3, 2),
+		})
+	}
+	
+	/// Same as [`HersheyFont::glyph_for`], with a binary search through the char map.
+	pub fn glyph_for(&self, c: char) -> Option<PackedGlyph<'a>> {
+		if self.flags() & ASCII_ORDER != 0 {
+			if !c.is_ascii() { return None; }
+			return self.glyph((c as u8).saturating_sub(b' ') as usize);
+		}
+		
+		let count = self.section(4).1;
+		let (mut lo, mut hi) = (0, count);
+		while lo < hi {
+			let mid = (lo + hi) / 2;
+			if self.map_entry(mid).0 < c as u32 { lo = mid + 1; } else { hi = mid; }
+		}
+		
+		if lo < count && self.map_entry(lo).0 == c as u32 {
+			self.glyph(self.map_entry(lo).1 as usize)
+		} else {
+			None
+		}
+	}
+	
+	/// How much to add to the space between `left` and `right`. Zero if
+	/// they're not a kerning pair.
+	pub fn kerning(&self, left: char, right: char) -> i32 {
+		let key = (left as u32, right as u32);
+		let count = self.section(5).1;
+		let (mut lo, mut hi) = (0, count);
+		while lo < hi {
+			let mid = (lo + hi) / 2;
+			let (l, r, _) = self.kern_entry(mid);
+			if (l, r) < key { lo = mid + 1; } else { hi = mid; }
+		}
+		
+		match (lo < count).then(|| self.kern_entry(lo)) {
+			Some((l, r, adjust)) if (l, r) == key => adjust,
+			_ => 0,
+		}
+	}
+	
+	/// Every kerning pair, sorted.
+	pub fn kerning_pairs(&self) -> Vec<(char, char, i32)> {
+		(0..self.section(5).1)
+			.map(|k| self.kern_entry(k))
+			.filter_map(|(l, r, adjust)| Some((char::from_u32(l)?, char::from_u32(r)?, adjust)))
+			.collect()
+	}
+	
+	/// Copy everything out into a regular [`HersheyFont`].
+	pub fn to_font(&self) -> HersheyFont {
+		let chars = (0..self.len())
+			.filter_map(|i| self.glyph(i))
+			.map(|g| g.to_char())
+			.collect::<Vec<_>>();
+		
+		let unicode = if self.flags() & ASCII_ORDER != 0 { None } else {
+			let mut unicode = vec![None; chars.len()];
+			for m in 0..self.section(4).1 {
+				let (c, i) = self.map_entry(m);
+				unicode[i as usize] = char::from_u32(c);
+			}
+			Some(unicode)
+		};
+		
+		HersheyFont { unicode, ..HersheyFont::new(self.name(), chars) }
+	}
+}
+
+/// One glyph in a [`PackedFont`].
+#[derive(Debug, Clone, Copy)]
+pub struct PackedGlyph<'a> {
+	pub id: usize,
+	pub left_hand: i8,
+	pub right_hand: i8,
+	start: usize,
+	ends: &'a [u8],
+	points: &'a [u8],
+}
+
+impl<'a> PackedGlyph<'a> {
+	/// Same as [`HersheyChar::advance`].
+	pub fn advance(&self) -> i32 {
+		self.right_hand as i32 - self.left_hand as i32
+	}
+	
+	/// Each stroke's points, straight out of the pack. These can be empty.
+	pub fn raw_strokes(&self) -> impl Iterator<Item = impl Iterator<Item = (i8, i8)> + 'a> + 'a {
+		let points = self.points;
+		let mut start = self.start;
+		
+		self.ends.chunks_exact(4).map(move |end| {
+			let end = u32_at(end, 0) as usize;
+			let run = &points[start * 2..end * 2];
+			start = end;
+			run.chunks_exact(2).map(|p| (p[0] as i8, p[1] as i8))
+		})
+	}
+	
+	/// Same as [`HersheyChar::strokes`].
+	pub fn strokes(&self) -> Vec<Stroke> {
+		self.raw_strokes()
+			.map(|run| run.map(|(x, y)| (x as f64, y as f64)).collect::<Vec<Point>>())
+			.filter(|run| !run.is_empty())
+			.collect()
+	}
+	
+	/// Copy this out into a regular [`HersheyChar`].
+	pub fn to_char(&self) -> HersheyChar {
+		let mut vertex_data = Vec::new();
+		for (i, run) in self.raw_strokes().enumerate() {
+			if i > 0 { vertex_data.push(None); }
+			vertex_data.extend(run.map(Some));
+		}
+		
+		HersheyChar {
+			id: self.id,
+			vertex_num: vertex_data.len(),
+			left_hand: self.left_hand,
+			right_hand: self.right_hand,
+			vertex_data,
+		}
+	}
+}
+
+impl<'a> TryFrom<&'a [u8]> for Pack<'a> {
+	type Error = HersheyError;
+	
+	fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+		Pack::new(data)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	
+	#[test]
+	fn checksum() {
+		assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+	}
+	
+	#[test]
+	fn reads_in_place() -> Result<(), HersheyError> {
+		let font = test_font("futural");
+		let kerning = [('A', 'V', -2), ('T', 'o', -3)];
+		let bytes = to_pack(&[PackFont { font: &font, kerning: &kerning }])?;
+		
+		assert_eq!(&bytes[..8], b"HRSHYPK\0");
+		assert_eq!(bytes.len() % 4, 0);
+		
+		let pack = Pack::new(&bytes)?;
+		assert_eq!(pack.len(), 1);
+		
+		let packed = pack.find("futural").unwrap();
+		assert_eq!(packed.len(), font.chars.len());
+		
+		let bang = packed.glyph_for('!').unwrap();
+		assert_eq!(bang.strokes(), font.glyph_for('!').unwrap().strokes());
+		assert_eq!(bang.advance(), 10);
+		assert!(packed.glyph_for('é').is_none());
+		
+		assert_eq!(packed.kerning('T', 'o'), -3);
+		assert_eq!(packed.kerning('o', 'T'), 0);
+		assert_eq!(packed.kerning_pairs(), kerning);
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn round_trip() -> Result<(), HersheyError> {
+		use std::fs::{read_dir, read_to_string};
+		use std::ffi::OsStr;
+		
+		let mut fonts = Vec::new();
+		for entry in read_dir("../fonts/").unwrap() {
+			let path = entry.unwrap().path();
+			
+			if path.is_file()
+			&& path.extension().and_then(OsStr::to_str) == Some("jhf") {
+				let jhf = read_to_string(&path).unwrap();
+				let name = path.file_name().unwrap().to_string_lossy();
+				fonts.push((HersheyFont::new_from_str(name, &jhf)?, jhf));
+			}
+		}
+		
+		// and one that isn't in ASCII order, with a doubled-up pen-up
+		let odd = HersheyChar::new_from_str("    7  7JZPFPF R RTFTF")?;
+		let odd = HersheyFont {
+			unicode: Some(vec![None, Some('α'), Some(' ')]),
+			..HersheyFont::new("odd", vec![odd.clone(), odd.clone(), odd])
+		};
+		let jhf = odd.to_jhf()?;
+		fonts.push((odd, jhf));
+		
+		let bytes = to_pack(&fonts.iter().map(|(f, _)| PackFont::from(f)).collect::<Vec<_>>())?;
+		let pack = Pack::new(&bytes)?;
+		assert_eq!(pack.len(), fonts.len());
+		
+		for ((font, jhf), packed) in fonts.iter().zip(pack.fonts()) {
+			let back = packed.to_font();
+			assert_eq!(&back, font, "{}", font.name);
+			assert_eq!(&back.to_jhf()?, jhf);
+			
+			for (c, _) in font.char_map() {
+				assert_eq!(packed.glyph_for(c).map(|g| g.to_char()).as_ref(), font.glyph_for(c));
+			}
+		}
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn catches_damage() {
+		let bytes = to_pack(&[PackFont::from(&test_font("futural"))]).unwrap();
+		assert!(Pack::new(&bytes).is_ok());
+		
+		let mut flipped = bytes.clone();
+		flipped[1000] ^= 1;
+		assert_eq!(Pack::new(&flipped).err(), Some(HersheyError::InvalidPack));
+		
+		assert_eq!(Pack::new(&bytes[..bytes.len() - 4]).err(), Some(HersheyError::InvalidPack));
+		assert_eq!(Pack::new(b"not a pack").err(), Some(HersheyError::InvalidPack));
+		
+		// a checksum that's right doesn't make the offsets right
+		let mut lying = bytes;
+		lying[HEADER_SIZE + 4..][..4].copy_from_slice(&u32::MAX.to_le_bytes());
+		let crc = crc32(&lying[HEADER_SIZE..]);
+		lying[20..24].copy_from_slice(&crc.to_le_bytes());
+		assert_eq!(Pack::new(&lying).err(), Some(HersheyError::InvalidPack));
+	}
+	
+	#[test]
+	fn too_big() {
+		let mut font = test_font("futural");
+		font.chars[0].id = u32::MAX as usize + 1;
+		assert_eq!(to_pack(&[PackFont::from(&font)]), Err(HersheyError::OutOfRange));
+	}
+	
+	#[test]
+	fn memory_mapped() -> Result<(), HersheyError> {
+		let path = std::env::temp_dir().join(format!("hershey_pack_{}.hpk", std::process::id()));
+		std::fs::write(&path, to_pack(&[PackFont::from(&test_font("futural"))])?).unwrap();
+		
+		let file = std::fs::File::open(&path).unwrap();
+		let map = unsafe { memmap2::Mmap::map(&file) }.unwrap();
+		let pack = Pack::new(&map)?;
+		assert_eq!(pack.font(0).unwrap().to_font(), test_font("futural"));
+		
+		drop(map);
+		std::fs::remove_file(path).unwrap();
+		Ok(())
+	}
+}
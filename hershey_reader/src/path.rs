@@ -0,0 +1,327 @@
+use core::f64::consts::{FRAC_PI_2, TAU};
+
+use crate::Point;
+use crate::layout::{line_width, Glyphs, PlacedChar};
+use crate::transform::Transform;
+
+/// One piece of a [`TextPath`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+	Line(Point, Point),
+	
+	/// Angles are in radians, measured like [`Transform::rotate`]:
+	/// with +y down, a positive `sweep` goes clockwise on screen.
+	Arc {
+		center: Point,
+		radius: f64,
+		start: f64,
+		sweep: f64,
+	},
+}
+
+impl Segment {
+	pub fn length(&self) -> f64 {
+		match *self {
+			Segment::Line(a, b) => (b.0 - a.0).hypot(b.1 - a.1),
+			Segment::Arc { radius, sweep, .. } => (radius * sweep).abs(),
+		}
+	}
+	
+	/// Point `d` units along this segment, and the direction of travel there
+	/// (as an angle). `d` can go past either end; it just keeps going straight.
+	pub fn sample(&self, d: f64) -> (Point, f64) {
+		match *self {
+			Segment::Line(a, b) => {
+				let len = self.length();
+				let (dx, dy) = if len > 0.0 {
+					((b.0 - a.0) / len, (b.1 - a.1) / len)
+				} else {
+					(1.0, 0.0)
+				};
+				((a.0 + dx * d, a.1 + dy * d), dy.atan2(dx))
+			},
+			Segment::Arc { center, radius, start, sweep } => {
+				let len = self.length();
+				let dir = if sweep < 0.0 { -1.0 } else { 1.0 };
+				
+				// a zero radius arc is just a point, so don't turn at all
+				let turn = |d: f64| if radius == 0.0 { 0.0 } else { dir * d / radius };
+				let at = |angle: f64| (
+					center.0 + radius * angle.cos(),
+					center.1 + radius * angle.sin(),
+				);
+				
+				if d < 0.0 || d > len {
+					// off the end: walk along the tangent instead of the circle
+					let (end, d) = if d < 0.0 { (0.0, d) } else { (len, d - len) };
+					let angle = start + turn(end);
+					let heading = angle + dir * FRAC_PI_2;
+					let p = at(angle);
+					return ((p.0 + heading.cos() * d, p.1 + heading.sin() * d), heading);
+				}
+				
+				let angle = start + turn(d);
+				(at(angle), angle + dir * FRAC_PI_2)
+			},
+		}
+	}
+	
+	fn start_point(&self) -> Point {
+		self.sample(0.0).0
+	}
+	
+	fn end_point(&self) -> Point {
+		self.sample(self.length()).0
+	}
+}
+
+/// Which side of a circle the text should sit on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircleSide {
+	/// Tops of the letters point away from the center. Reads left to right
+	/// across the top of the circle.
+	Outside,
+	/// Tops of the letters point at the center. Reads left to right
+	/// across the bottom of the circle.
+	Inside,
+}
+
+/// A bunch of [`Segment`]s, end to end, for text to follow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextPath {
+	pub segments: Vec<Segment>,
+}
+
+impl TextPath {
+	pub fn new(segments: Vec<Segment>) -> Self {
+		TextPath { segments }
+	}
+	
+	pub fn line(a: Point, b: Point) -> Self {
+		Self::new(vec![Segment::Line(a, b)])
+	}
+	
+	/// Straight lines through every point in order.
+	pub fn polyline(points: &[Point]) -> Self {
+		Self::new(points.windows(2)
+			.map(|pair| Segment::Line(pair[0], pair[1]))
+			.collect())
+	}
+	
+	pub fn arc(center: Point, radius: f64, start: f64, sweep: f64) -> Self {
+		Self::new(vec![Segment::Arc { center, radius, start, sweep }])
+	}
+	
+	/// A full circle that starts (and ends) at `angle`.
+	pub fn circle(center: Point, radius: f64, angle: f64, side: CircleSide) -> Self {
+		let sweep = match side {
+			CircleSide::Outside => TAU,
+			CircleSide::Inside => -TAU,
+		};
+		Self::arc(center, radius, angle, sweep)
+	}
+	
+	pub fn length(&self) -> f64 {
+		self.segments.iter().map(Segment::length).sum()
+	}
+	
+	/// Does it end where it started?
+	pub fn is_closed(&self) -> bool {
+		match (self.segments.first(), self.segments.last()) {
+			(Some(first), Some(last)) => {
+				let (a, b) = (first.start_point(), last.end_point());
+				(a.0 - b.0).hypot(a.1 - b.1) < 1e-9
+			},
+			_ => false,
+		}
+	}
+	
+	/// Point `d` units along the path, and the direction of travel there.
+	/// Closed paths wrap around; open ones keep going straight off the ends.
+	pub fn sample(&self, d: f64) -> Option<(Point, f64)> {
+		let len = self.length();
+		let mut d = if self.is_closed() && len > 0.0 { d.rem_euclid(len) } else { d };
+		
+		let last = self.segments.len().checked_sub(1)?;
+		for (i, seg) in self.segments.iter().enumerate() {
+			let seg_len = seg.length();
+			if d <= seg_len || i == last {
+				return Some(seg.sample(d));
+			}
+			d -= seg_len;
+		}
+		
+		unreachable!()
+	}
+}
+
+/// Where the text goes along the path.
+///
+/// On a closed path there's no "middle", so the start of the path is the
+/// anchor: `Center` centers the text on it and `End` finishes right before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathAlign {
+	#[default]
+	Start,
+	Center,
+	End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathOptions {
+	pub align: PathAlign,
+	
+	/// Glyph size, same as [`Transform::uniform_scale`]. Advances scale with it.
+	pub scale: f64,
+	
+	/// Lift the glyphs this far off the path (in font units, towards their
+	/// tops). With `0.0` the path runs through each glyph's origin.
+	pub offset: f64,
+}
+
+impl Default for PathOptions {
+	fn default() -> Self {
+		PathOptions {
+			align: PathAlign::Start,
+			scale: 1.0,
+			offset: 0.0,
+		}
+	}
+}
+
+/// Lay out `text` as one line that follows `path`. Each glyph sits where
+/// its middle lands along the path, turned to match the path there.
+/// Newlines and characters the font doesn't have are skipped.
+pub fn layout_on_path<'a, F: Glyphs + ?Sized>(font: &'a F, text: &str, path: &TextPath, options: &PathOptions) -> Vec<PlacedChar<'a>> {
+	let width = line_width(font, text) * options.scale;
+	let length = path.length();
+	
+	let mut pen = match (options.align, path.is_closed()) {
+		(PathAlign::Start, _) => 0.0,
+		(PathAlign::Center, false) => (length - width) / 2.0,
+		(PathAlign::Center, true) => -width / 2.0,
+		(PathAlign::End, false) => length - width,
+		(PathAlign::End, true) => -width,
+	};
+	
+	let mut placed = Vec::new();
+	
+	for (index, ch) in text.chars().enumerate() {
+		if ch == '\n' { continue; }
+		
+		let (chr, scale) = match font.sized_glyph(ch) {
+			Some((chr, scale)) => (chr, scale * options.scale),
+			None => continue,
+		};
+		let advance = chr.advance() as f64 * scale;
+		
+		let (point, heading) = match path.sample(pen + advance / 2.0) {
+			Some(it) => it,
+			None => return placed,
+		};
+		
+		let middle = (chr.left_hand as f64 + chr.right_hand as f64) / 2.0;
+		let transform = Transform::translate(-middle, -options.offset)
+			.then(&Transform::uniform_scale(scale))
+			.then(&Transform::rotate(heading))
+			.then(&Transform::translate(point.0, point.1));
+		
+		placed.push(PlacedChar {
+			chr, transform,
+			line: 0, index,
+		});
+		pen += advance;
+	}
+	
+	placed
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::HersheyFont;
+	
+	fn close(a: Point, b: Point) -> bool {
+		(a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+	}
+	
+	fn font() -> HersheyFont {
+		// space and !
+		HersheyFont::new_from_str("test", "12345  1JZ\n12345  9MWRFRT RRYQZR[SZRY").unwrap()
+	}
+	
+	#[test]
+	fn polyline_sampling() {
+		let path = TextPath::polyline(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+		assert_eq!(path.length(), 20.0);
+		assert!(!path.is_closed());
+		
+		let (p, heading) = path.sample(15.0).unwrap();
+		assert!(close(p, (10.0, 5.0)));
+		assert!((heading - FRAC_PI_2).abs() < 1e-9);
+		
+		// runs off the end in a straight line
+		let (p, _) = path.sample(25.0).unwrap();
+		assert!(close(p, (10.0, 15.0)));
+	}
+	
+	#[test]
+	fn circle_wraps() {
+		let path = TextPath::circle((0.0, 0.0), 10.0, -FRAC_PI_2, CircleSide::Outside);
+		assert!(path.is_closed());
+		
+		// top of the circle, heading right
+		let (p, heading) = path.sample(path.length() * 3.0).unwrap();
+		assert!(close(p, (0.0, -10.0)));
+		assert!(close((heading.cos(), heading.sin()), (1.0, 0.0)));
+		
+		let inside = TextPath::circle((0.0, 0.0), 10.0, FRAC_PI_2, CircleSide::Inside);
+		// bottom of the circle, also heading right
+		let (p, heading) = inside.sample(0.0).unwrap();
+		assert!(close(p, (0.0, 10.0)));
+		assert!(heading.abs() < 1e-9);
+		
+		// no radius is just a point, not NaN
+		let dot = TextPath::arc((3.0, 4.0), 0.0, 0.0, 1.0);
+		assert_eq!(dot.length(), 0.0);
+		assert_eq!(dot.sample(0.0), Some(((3.0, 4.0), FRAC_PI_2)));
+		let (p, heading) = dot.sample(2.0).unwrap();
+		assert!(close(p, (3.0, 6.0)) && heading == FRAC_PI_2);
+	}
+	
+	#[test]
+	fn alignment_on_a_line() {
+		let font = font();
+		let path = TextPath::line((0.0, 0.0), (100.0, 0.0));
+		
+		let start = layout_on_path(&font, "!", &path, &PathOptions::default());
+		assert!(close(start[0].transform.apply((0.0, 0.0)), (5.0, 0.0)));
+		
+		let center = PathOptions { align: PathAlign::Center, ..Default::default() };
+		let center = layout_on_path(&font, "!!", &path, &center);
+		assert!(close(center[0].transform.apply((0.0, 0.0)), (45.0, 0.0)));
+		assert!(close(center[1].transform.apply((0.0, 0.0)), (55.0, 0.0)));
+		
+		let end = PathOptions { align: PathAlign::End, scale: 2.0, offset: 1.0 };
+		let end = layout_on_path(&font, "!", &path, &end);
+		assert!(close(end[0].transform.apply((0.0, 0.0)), (90.0, -2.0)));
+	}
+	
+	#[test]
+	fn glyphs_follow_the_tangent() {
+		let font = font();
+		let path = TextPath::circle((0.0, 0.0), 50.0, -FRAC_PI_2, CircleSide::Outside);
+		let options = PathOptions { align: PathAlign::Center, ..Default::default() };
+		
+		let placed = layout_on_path(&font, "!", &path, &options);
+		// centered on the top, upright
+		assert!(close(placed[0].transform.apply((0.0, 0.0)), (0.0, -50.0)));
+		assert!(close(placed[0].transform.apply((0.0, -12.0)), (0.0, -62.0)));
+		
+		// further round the circle, the glyph leans clockwise
+		let quarter = layout_on_path(&font, " !", &path, &PathOptions { align: PathAlign::Start, ..options });
+		let origin = quarter[1].transform.apply((0.0, 0.0));
+		let top = quarter[1].transform.apply((0.0, -1.0));
+		assert!(top.0 > origin.0);
+	}
+}
@@ -0,0 +1,254 @@
+use crate::{Point, Stroke};
+use crate::layout::PlacedChar;
+
+/// A stroke, plus which glyph and line it came from so the optimiser
+/// knows what it's allowed to shuffle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlotStroke {
+	pub points: Stroke,
+	pub glyph: usize,
+	pub line: usize,
+	/// Which pen to draw it with. The optimiser never mixes pens up.
+	pub pen: usize,
+}
+
+/// Turn laid out text into plottable strokes, in drawing order, all with
+/// pen 1. See [`rich::plot_strokes`](crate::rich::plot_strokes) for more pens.
+pub fn plot_strokes(placed: &[PlacedChar]) -> Vec<PlotStroke> {
+	placed.iter()
+		.enumerate()
+		.flat_map(|(glyph, p)| p.strokes()
+			.into_iter()
+			.map(move |points| PlotStroke { points, glyph, line: p.line, pen: 1 }))
+		.collect()
+}
+
+/// How much of the original order [`optimize_travel`] has to keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeepOrder {
+	/// Anything goes.
+	#[default]
+	Nothing,
+	/// Glyphs get drawn in order, but their strokes can move around.
+	Glyphs,
+	/// Lines get drawn in order, but the glyphs in them can move around.
+	Lines,
+}
+
+/// Pen-up distance before and after optimising.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TravelReport {
+	pub before: f64,
+	pub after: f64,
+}
+
+fn dist(a: Point, b: Point) -> f64 {
+	(b.0 - a.0).hypot(b.1 - a.1)
+}
+
+fn first(s: &PlotStroke) -> Point {
+	s.points.first().copied().unwrap_or((0.0, 0.0))
+}
+
+fn last(s: &PlotStroke) -> Point {
+	s.points.last().copied().unwrap_or((0.0, 0.0))
+}
+
+/// How far the pen travels in the air, starting from `start`.
+pub fn pen_up_distance(strokes: &[PlotStroke], start: Point) -> f64 {
+	let mut pen = start;
+	let mut total = 0.0;
+	
+	for s in strokes.iter().filter(|s| !s.points.is_empty()) {
+		total += dist(pen, first(s));
+		pen = last(s);
+	}
+	
+	total
+}
+
+/// Reorder (and flip) strokes so the pen spends less time in the air.
+/// Goes nearest-neighbour first, then cleans that up with 2-opt. Each run
+/// of strokes with the same pen is done on its own, so sort by pen first.
+/// Anything that doesn't come out shorter is left how it was, so `after` is
+/// never more than `before`.
+pub fn optimize_travel(strokes: &mut Vec<PlotStroke>, keep: KeepOrder, start: Point) -> TravelReport {
+	strokes.retain(|s| !s.points.is_empty());
+	let before = pen_up_distance(strokes, start);
+	let original = strokes.clone();
+	
+	let same_group = |a: &PlotStroke, b: &PlotStroke| a.pen == b.pen && match keep {
+		KeepOrder::Nothing => true,
+		KeepOrder::Glyphs => a.glyph == b.glyph,
+		KeepOrder::Lines => a.line == b.line,
+	};
+	
+	let mut pen = start;
+	let mut from = 0;
+	while from < strokes.len() {
+		let to = from + strokes[from..].iter()
+			.position(|s| !same_group(&strokes[from], s))
+			.unwrap_or(strokes.len() - from);
+		
+		// nearest-neighbour can lose to the order things came in, and 2-opt
+		// only ever improves on that
+		let group = &mut strokes[from..to];
+		let as_given = group.to_vec();
+		nearest_neighbour(group, pen);
+		two_opt(group, pen);
+		if pen_up_distance(group, pen) >= pen_up_distance(&as_given, pen) {
+			group.clone_from_slice(&as_given);
+		}
+		
+		pen = last(&strokes[to - 1]);
+		from = to;
+	}
+	
+	// a group ending somewhere else can make the next one worse
+	let mut after = pen_up_distance(strokes, start);
+	if after > before {
+		*strokes = original;
+		after = before;
+	}
+	
+	TravelReport { before, after }
+}
+
+fn nearest_neighbour(strokes: &mut [PlotStroke], start: Point) {
+	let mut pen = start;
+	
+	for i in 0..strokes.len() {
+		let mut best = (f64::INFINITY, i, false);
+		
+		for (j, s) in strokes.iter().enumerate().skip(i) {
+			let fwd = dist(pen, first(s));
+			let rev = dist(pen, last(s));
+			if fwd < best.0 { best = (fwd, j, false); }
+			if rev < best.0 { best = (rev, j, true); }
+		}
+		
+		let (_, j, flip) = best;
+		strokes.swap(i, j);
+		if flip { strokes[i].points.reverse(); }
+		pen = last(&strokes[i]);
+	}
+}
+
+/// Reversing `strokes[i..=j]` (order *and* direction) only changes the two
+/// pen-up moves at its ends, so each try is cheap. `i == j` is just a flip.
+fn two_opt(strokes: &mut [PlotStroke], start: Point) {
+	const MAX_PASSES: usize = 32;
+	let n = strokes.len();
+	
+	for _ in 0..MAX_PASSES {
+		let mut improved = false;
+		
+		for i in 0..n {
+			let before_i = if i == 0 { start } else { last(&strokes[i - 1]) };
+			
+			for j in i..n {
+				let (si, ej) = (first(&strokes[i]), last(&strokes[j]));
+				
+				let mut old = dist(before_i, si);
+				let mut new = dist(before_i, ej);
+				if let Some(next) = strokes.get(j + 1) {
+					old += dist(ej, first(next));
+					new += dist(si, first(next));
+				}
+				
+				if new + 1e-9 < old {
+					strokes[i..=j].reverse();
+					for s in &mut strokes[i..=j] {
+						s.points.reverse();
+					}
+					improved = true;
+				}
+			}
+		}
+		
+		if !improved { break; }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn stroke(points: &[Point], glyph: usize) -> PlotStroke {
+		PlotStroke { points: points.to_vec(), glyph, line: 0, pen: 1 }
+	}
+	
+	#[test]
+	fn reorders_and_flips() {
+		let mut strokes = vec![
+			stroke(&[(20.0, 0.0), (30.0, 0.0)], 0),
+			stroke(&[(10.0, 0.0), (0.0, 0.0)], 1),
+			stroke(&[(30.0, 5.0), (20.0, 5.0)], 2),
+		];
+		
+		let report = optimize_travel(&mut strokes, KeepOrder::Nothing, (0.0, 0.0));
+		
+		assert_eq!(report.before, 20.0 + 20.0 + 925f64.sqrt());
+		assert_eq!(report.after, 10.0 + 5.0);
+		assert_eq!(strokes[0].points, vec![(0.0, 0.0), (10.0, 0.0)]);
+		assert_eq!(strokes[1].glyph, 0);
+		assert_eq!(strokes[2].points, vec![(30.0, 5.0), (20.0, 5.0)]);
+	}
+	
+	#[test]
+	fn keeps_glyph_order() {
+		let mut strokes = vec![
+			stroke(&[(100.0, 0.0), (110.0, 0.0)], 0),
+			stroke(&[(0.0, 0.0), (10.0, 0.0)], 1),
+			stroke(&[(50.0, 0.0), (40.0, 0.0)], 1),
+		];
+		
+		let report = optimize_travel(&mut strokes, KeepOrder::Glyphs, (0.0, 0.0));
+		
+		assert!(report.after <= report.before);
+		assert_eq!(strokes[0].glyph, 0);
+		// glyph 1 starts from where glyph 0 left off, so the far one goes first
+		assert_eq!(strokes[1].points, vec![(50.0, 0.0), (40.0, 0.0)]);
+	}
+	
+	#[test]
+	fn never_worse_on_real_text() {
+		let font = crate::test_font("futural");
+		let placed = crate::layout::layout_str(&font, "Hershey\nFonts 1234567890", &Default::default());
+		
+		let mut strokes = plot_strokes(&placed);
+		let count = strokes.len();
+		let report = optimize_travel(&mut strokes, KeepOrder::Nothing, (0.0, 0.0));
+		
+		assert_eq!(strokes.len(), count);
+		assert!(report.after < report.before);
+		assert_eq!(report.after, pen_up_distance(&strokes, (0.0, 0.0)));
+	}
+	
+	#[test]
+	fn leaves_good_orders_alone() {
+		// already as short as it gets
+		let mut strokes = vec![
+			stroke(&[(0.0, 0.0), (10.0, 0.0)], 0),
+			stroke(&[(10.0, 5.0), (0.0, 5.0)], 1),
+			stroke(&[(0.0, 10.0), (10.0, 10.0)], 2),
+		];
+		let given = strokes.clone();
+		let report = optimize_travel(&mut strokes, KeepOrder::Nothing, (0.0, 0.0));
+		assert_eq!((report.before, report.after), (10.0, 10.0));
+		assert_eq!(strokes, given);
+		
+		// nearest-neighbour goes wrong on this one, and 2-opt can't fix it
+		let mut strokes = vec![
+			stroke(&[(3.0, 5.0), (8.0, 0.0)], 0),
+			stroke(&[(4.0, 2.0), (7.0, 19.0)], 1),
+			stroke(&[(3.0, 19.0), (13.0, 7.0)], 2),
+			stroke(&[(18.0, 10.0), (19.0, 15.0)], 3),
+		];
+		let given = strokes.clone();
+		let report = optimize_travel(&mut strokes, KeepOrder::Nothing, (0.0, 0.0));
+		assert_eq!(report.after, report.before);
+		assert_eq!(strokes, given);
+	}
+}
@@ -0,0 +1,227 @@
+//! Things to do to a [`Stroke`] once it's out of the integer grid.
+//!
+//! Strokes that end where they start (like an `O`) are treated as loops,
+//! so smoothing doesn't leave a corner where the ends meet.
+
+use crate::{Point, Stroke};
+
+fn dist(a: Point, b: Point) -> f64 {
+	(b.0 - a.0).hypot(b.1 - a.1)
+}
+
+fn lerp(a: Point, b: Point, t: f64) -> Point {
+	(a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+fn is_loop(stroke: &[Point]) -> bool {
+	stroke.len() > 3 && stroke.first() == stroke.last()
+}
+
+/// How far `p` is from the segment `a`–`b`.
+fn segment_dist(p: Point, a: Point, b: Point) -> f64 {
+	let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+	let len2 = dx * dx + dy * dy;
+	if len2 == 0.0 { return dist(p, a); }
+	
+	let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0);
+	dist(p, lerp(a, b, t))
+}
+
+/// Douglas–Peucker: drop points until the stroke would move more than
+/// `tolerance` away from the original. The ends always stay.
+pub fn simplify(stroke: &[Point], tolerance: f64) -> Stroke {
+	if stroke.len() < 3 { return stroke.to_vec(); }
+	
+	let mut keep = vec![false; stroke.len()];
+	keep[0] = true;
+	keep[stroke.len() - 1] = true;
+	
+	let mut todo = vec![(0, stroke.len() - 1)];
+	while let Some((from, to)) = todo.pop() {
+		let (a, b) = (stroke[from], stroke[to]);
+		
+		let far = (from + 1..to)
+			.map(|i| (i, segment_dist(stroke[i], a, b)))
+			.max_by(|x, y| x.1.total_cmp(&y.1));
+		
+		if let Some((i, d)) = far {
+			if d > tolerance {
+				keep[i] = true;
+				todo.push((from, i));
+				todo.push((i, to));
+			}
+		}
+	}
+	
+	stroke.iter()
+		.zip(keep)
+		.filter(|(_, k)| *k)
+		.map(|(p, _)| *p)
+		.collect()
+}
+
+/// Chaikin corner cutting, `levels` times over. Every level cuts each corner
+/// at 1/4 and 3/4 of the way along, so the stroke rounds off and shrinks in
+/// a little. Open strokes keep their ends.
+pub fn chaikin(stroke: &[Point], levels: usize) -> Stroke {
+	let mut cur = stroke.to_vec();
+	
+	for _ in 0..levels {
+		if cur.len() < 3 { break; }
+		let closed = is_loop(&cur);
+		
+		let mut next = Vec::with_capacity(cur.len() * 2);
+		if !closed { next.push(cur[0]); }
+		for pair in cur.windows(2) {
+			next.push(lerp(pair[0], pair[1], 0.25));
+			next.push(lerp(pair[0], pair[1], 0.75));
+		}
+		if closed {
+			next.push(next[0]);
+		} else {
+			next.push(cur[cur.len() - 1]);
+		}
+		
+		cur = next;
+	}
+	
+	cur
+}
+
+/// Catmull–Rom spline through every point, with `subdivisions` extra points
+/// between each pair. Unlike [`chaikin`], it goes through the originals.
+pub fn catmull_rom(stroke: &[Point], subdivisions: usize) -> Stroke {
+	if stroke.len() < 3 || subdivisions == 0 { return stroke.to_vec(); }
+	
+	let closed = is_loop(stroke);
+	let n = stroke.len();
+	let at = |i: isize| -> Point {
+		if closed {
+			// the last point is the first point again, so wrap over n - 1
+			stroke[i.rem_euclid(n as isize - 1) as usize]
+		} else {
+			stroke[i.clamp(0, n as isize - 1) as usize]
+		}
+	};
+	
+	let mut out = Vec::with_capacity((n - 1) * (subdivisions + 1) + 1);
+	for i in 0..n as isize - 1 {
+		let (p0, p1, p2, p3) = (at(i - 1), at(i), at(i + 1), at(i + 2));
+		
+		for step in 0..=subdivisions {
+			let t = step as f64 / (subdivisions + 1) as f64;
+			let (t2, t3) = (t * t, t * t * t);
+			let blend = |a: f64, b: f64, c: f64, d: f64| 0.5 * (
+				2.0 * b
+				+ (c - a) * t
+				+ (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+				+ (3.0 * b - a - 3.0 * c + d) * t3
+			);
+			out.push((
+				blend(p0.0, p1.0, p2.0, p3.0),
+				blend(p0.1, p1.1, p2.1, p3.1),
+			));
+		}
+	}
+	out.push(stroke[n - 1]);
+	
+	out
+}
+
+/// Total length along the stroke.
+pub fn length(stroke: &[Point]) -> f64 {
+	stroke.windows(2).map(|pair| dist(pair[0], pair[1])).sum()
+}
+
+/// New points every `spacing` units along the stroke. The last gap takes up
+/// whatever's left over, so both ends stay where they were.
+pub fn resample(stroke: &[Point], spacing: f64) -> Stroke {
+	let total = length(stroke);
+	if stroke.len() < 2 || spacing <= 0.0 || total == 0.0 {
+		return stroke.to_vec();
+	}
+	
+	let steps = (total / spacing).round().max(1.0) as usize;
+	let step = total / steps as f64;
+	
+	let mut out = Vec::with_capacity(steps + 1);
+	out.push(stroke[0]);
+	
+	let mut seg = 0;
+	let mut seg_start = 0.0;
+	for i in 1..steps {
+		let want = step * i as f64;
+		
+		loop {
+			let seg_len = dist(stroke[seg], stroke[seg + 1]);
+			if seg_start + seg_len >= want || seg + 2 == stroke.len() {
+				let t = if seg_len > 0.0 { (want - seg_start) / seg_len } else { 0.0 };
+				out.push(lerp(stroke[seg], stroke[seg + 1], t));
+				break;
+			}
+			seg_start += seg_len;
+			seg += 1;
+		}
+	}
+	
+	out.push(stroke[stroke.len() - 1]);
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn simplify_drops_the_wiggles() {
+		let stroke = vec![(0.0, 0.0), (1.0, 0.1), (2.0, -0.1), (3.0, 5.0), (4.0, 0.0)];
+		
+		assert_eq!(simplify(&stroke, 0.5), vec![(0.0, 0.0), (2.0, -0.1), (3.0, 5.0), (4.0, 0.0)]);
+		assert_eq!(simplify(&stroke, 10.0), vec![(0.0, 0.0), (4.0, 0.0)]);
+		assert_eq!(simplify(&stroke, 0.0), stroke);
+	}
+	
+	#[test]
+	fn chaikin_keeps_open_ends() {
+		let stroke = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0)];
+		let smooth = chaikin(&stroke, 2);
+		
+		assert_eq!(smooth.first(), Some(&(0.0, 0.0)));
+		assert_eq!(smooth.last(), Some(&(4.0, 4.0)));
+		assert!(!smooth.contains(&(4.0, 0.0)));
+	}
+	
+	#[test]
+	fn chaikin_closes_loops() {
+		let square = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+		let smooth = chaikin(&square, 1);
+		
+		assert_eq!(smooth.len(), 9);
+		assert_eq!(smooth.first(), smooth.last());
+		assert!(!smooth.contains(&(0.0, 0.0)));
+	}
+	
+	#[test]
+	fn catmull_rom_goes_through_the_points() {
+		let stroke = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (8.0, 4.0)];
+		let smooth = catmull_rom(&stroke, 3);
+		
+		assert_eq!(smooth.len(), 3 * 4 + 1);
+		for (i, p) in stroke.iter().enumerate() {
+			let q = smooth[i * 4];
+			assert!(dist(*p, q) < 1e-9);
+		}
+	}
+	
+	#[test]
+	fn resample_evenly() {
+		let stroke = vec![(0.0, 0.0), (3.0, 0.0), (3.0, 7.0)];
+		let even = resample(&stroke, 1.0);
+		
+		assert_eq!(even.len(), 11);
+		assert_eq!(even[3], (3.0, 0.0));
+		assert_eq!(even[5], (3.0, 2.0));
+		assert_eq!(even.last(), Some(&(3.0, 7.0)));
+		assert!((length(&even) - 10.0).abs() < 1e-9);
+	}
+}
@@ -0,0 +1,289 @@
+//! Text where every span can have its own font, size, colour, letter
+//! spacing and baseline shift, and the backends that care about colour:
+//! SVG gets stroke colours, plotters get pen numbers.
+
+use std::fmt::Write;
+
+use crate::HersheyFont;
+use crate::layout::{Glyphs, PlacedChar, BASELINE, LINE_HEIGHT};
+use crate::plot::PlotStroke;
+use crate::transform::Transform;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpanStyle {
+	/// Multiplies the glyphs' size, and how far they move the pen.
+	pub scale: f64,
+	
+	/// `0xRRGGBB`, same as the viewer.
+	pub color: u32,
+	
+	/// Which pen a plotter should draw it with.
+	pub pen: usize,
+	
+	/// Extra space after every character, in font units. Isn't scaled.
+	pub letter_spacing: f64,
+	
+	/// How far the baseline is raised, in font units. Negative lowers it.
+	pub baseline_shift: f64,
+}
+
+impl Default for SpanStyle {
+	fn default() -> Self {
+		SpanStyle {
+			scale: 1.0,
+			color: 0x000000,
+			pen: 1,
+			letter_spacing: 0.0,
+			baseline_shift: 0.0,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span<'a, F: ?Sized = HersheyFont> {
+	pub text: String,
+	pub font: &'a F,
+	pub style: SpanStyle,
+}
+
+/// A string made of [`Span`]s. Use `RichText<dyn Glyphs>` to mix plain
+/// fonts and [`FontStack`](crate::stack::FontStack)s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichText<'a, F: ?Sized = HersheyFont> {
+	pub spans: Vec<Span<'a, F>>,
+}
+
+impl<F: ?Sized> Default for RichText<'_, F> {
+	fn default() -> Self {
+		RichText { spans: Vec::new() }
+	}
+}
+
+impl<'a, F: ?Sized> RichText<'a, F> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	
+	/// Add a span on the end.
+	pub fn with(mut self, text: impl Into<String>, font: &'a F, style: SpanStyle) -> Self {
+		self.spans.push(Span { text: text.into(), font, style });
+		self
+	}
+	
+	/// All the text, without the styles.
+	pub fn plain_text(&self) -> String {
+		self.spans.iter().map(|span| span.text.as_str()).collect()
+	}
+}
+
+/// A [`PlacedChar`] and the style it's in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichChar<'a> {
+	pub placed: PlacedChar<'a>,
+	
+	/// Which of [`RichText::spans`] it's from.
+	pub span: usize,
+	
+	pub style: SpanStyle,
+}
+
+/// Lay out `text` left to right, one line per `\n` (which can be in the
+/// middle of a span), then put the whole thing through `t`. Lines with
+/// bigger text on them get more room: each one is [`LINE_HEIGHT`] times
+/// the average of its biggest scale and the one above's.
+/// [`PlacedChar::index`] counts through the [`plain_text`](RichText::plain_text).
+pub fn layout_rich<'a, F: Glyphs + ?Sized>(text: &RichText<'a, F>, t: &Transform) -> Vec<RichChar<'a>> {
+	// the biggest scale on each line, for spacing them out
+	let mut line_scales = vec![0.0f64];
+	for span in &text.spans {
+		for ch in span.text.chars() {
+			if ch == '\n' {
+				line_scales.push(0.0);
+			} else {
+				let last = line_scales.last_mut().unwrap();
+				*last = last.max(span.style.scale);
+			}
+		}
+	}
+	for scale in &mut line_scales {
+		if *scale == 0.0 { *scale = 1.0; }
+	}
+	
+	let mut placed = Vec::new();
+	let mut line = 0;
+	let mut pen_x = 0.0;
+	let mut pen_y = 0.0;
+	let mut index = 0;
+	
+	for (i, span) in text.spans.iter().enumerate() {
+		let style = span.style;
+		
+		for ch in span.text.chars() {
+			if ch == '\n' {
+				line += 1;
+				pen_x = 0.0;
+				pen_y += LINE_HEIGHT * (line_scales[line - 1] + line_scales[line]) / 2.0;
+			} else if let Some((chr, scale)) = span.font.sized_glyph(ch) {
+				let scale = scale * style.scale;
+				// scaled around the baseline, so every size sits on the same line
+				let origin = Transform::translate(-chr.left_hand as f64, -BASELINE)
+					.then(&Transform::uniform_scale(scale))
+					.then(&Transform::translate(pen_x, pen_y + BASELINE - style.baseline_shift));
+				
+				placed.push(RichChar {
+					placed: PlacedChar {
+						chr,
+						transform: origin.then(t),
+						line, index,
+					},
+					span: i,
+					style,
+				});
+				pen_x += chr.advance() as f64 * scale + style.letter_spacing;
+			}
+			index += 1;
+		}
+	}
+	
+	placed
+}
+
+/// Like [`plot::plot_strokes`](crate::plot::plot_strokes), but with each
+/// stroke's [`pen`](PlotStroke::pen) from its span. Everything's sorted by
+/// pen (and otherwise left in order) so each pen only gets picked up once.
+pub fn plot_strokes(chars: &[RichChar]) -> Vec<PlotStroke> {
+	let mut strokes: Vec<_> = chars.iter()
+		.enumerate()
+		.flat_map(|(glyph, c)| c.placed.strokes()
+			.into_iter()
+			.map(move |points| PlotStroke { points, glyph, line: c.placed.line, pen: c.style.pen }))
+		.collect();
+	strokes.sort_by_key(|s| s.pen);
+	strokes
+}
+
+/// An SVG document with all the strokes in, one `<path>` for each run of
+/// characters in the same colour, and a `viewBox` that fits around them.
+pub fn to_svg(chars: &[RichChar], stroke_width: f64) -> String {
+	let strokes: Vec<_> = chars.iter().map(|c| c.placed.strokes()).collect();
+	
+	let points = || strokes.iter().flatten().flatten();
+	let (mut x0, mut y0, mut x1, mut y1) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+	for &(x, y) in points() {
+		x0 = x0.min(x);
+		y0 = y0.min(y);
+		x1 = x1.max(x);
+		y1 = y1.max(y);
+	}
+	if x0 > x1 { (x0, y0, x1, y1) = (0.0, 0.0, 0.0, 0.0); }
+	let pad = stroke_width / 2.0;
+	
+	let mut svg = String::new();
+	writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+		x0 - pad, y0 - pad, x1 - x0 + 2.0 * pad, y1 - y0 + 2.0 * pad).unwrap();
+	
+	let mut i = 0;
+	while i < chars.len() {
+		let color = chars[i].style.color;
+		let run = chars[i..].iter().take_while(|c| c.style.color == color).count();
+		
+		let mut d = String::new();
+		for stroke in strokes[i..i + run].iter().flatten() {
+			for (j, &(x, y)) in stroke.iter().enumerate() {
+				write!(d, "{}{} {}", if j == 0 { "M" } else { " L" }, x, y).unwrap();
+			}
+			// lone points still want a dot
+			if stroke.len() == 1 { d.push_str(" z"); }
+		}
+		
+		if !d.is_empty() {
+			writeln!(svg, r##"<path d="{}" fill="none" stroke="#{:06x}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"##,
+				d, color & 0xFFFFFF, stroke_width).unwrap();
+		}
+		i += run;
+	}
+	
+	svg.push_str("</svg>\n");
+	svg
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	use crate::layout::layout_str;
+	
+	#[test]
+	fn lays_out_spans() {
+		let (futural, gothiceng) = (test_font("futural"), test_font("gothiceng"));
+		let red = SpanStyle { color: 0xFF0000, pen: 2, ..SpanStyle::default() };
+		let big = SpanStyle { scale: 2.0, letter_spacing: 1.0, baseline_shift: 3.0, ..SpanStyle::default() };
+		
+		let text = RichText::new()
+			.with("ab", &futural, SpanStyle::default())
+			.with("c\nd", &gothiceng, red)
+			.with("e", &futural, big);
+		assert_eq!(text.plain_text(), "abc\nde");
+		
+		let placed = layout_rich(&text, &Transform::IDENTITY);
+		assert_eq!(placed.len(), 5);
+		
+		// same as plain layout, as far as the styles don't change anything
+		let plain = layout_str(&futural, "ab", &Transform::IDENTITY);
+		assert_eq!(placed[1].placed, plain[1]);
+		assert_eq!((placed[2].placed.chr, placed[2].span, placed[2].style), (gothiceng.glyph_for('c').unwrap(), 1, red));
+		
+		// the line break in the middle of a span still starts a new line, and
+		// it's further down because of the big e
+		let d = &placed[3];
+		assert_eq!((d.placed.line, d.placed.index, d.style), (1, 4, red));
+		assert_eq!(d.placed.transform.apply((d.placed.chr.left_hand as f64, 0.0)), (0.0, LINE_HEIGHT * 1.5));
+		
+		let e = &placed[4];
+		let d_advance = d.placed.chr.advance() as f64;
+		assert_eq!(e.placed.transform.apply((e.placed.chr.left_hand as f64, BASELINE)), (d_advance, LINE_HEIGHT * 1.5 + BASELINE - 3.0));
+		assert_eq!(e.placed.transform.apply((1.0, 0.0)).0 - e.placed.transform.apply((0.0, 0.0)).0, 2.0);
+	}
+	
+	#[test]
+	fn sizes_share_a_baseline() {
+		let futural = test_font("futural");
+		let big = SpanStyle { scale: 2.0, ..SpanStyle::default() };
+		let text = RichText::new()
+			.with("x", &futural, SpanStyle::default())
+			.with("x", &futural, big);
+		let placed = layout_rich(&text, &Transform::IDENTITY);
+		
+		let bottom = |c: &RichChar| c.placed.strokes().iter().flatten().map(|&(_, y)| y).fold(f64::NEG_INFINITY, f64::max);
+		let top = |c: &RichChar| c.placed.strokes().iter().flatten().map(|&(_, y)| y).fold(f64::INFINITY, f64::min);
+		
+		// x sits on the baseline, and the big one does too
+		assert_eq!(bottom(&placed[0]), BASELINE);
+		assert_eq!(bottom(&placed[1]), BASELINE);
+		assert_eq!(BASELINE - top(&placed[1]), 2.0 * (BASELINE - top(&placed[0])));
+	}
+	
+	#[test]
+	fn colours_and_pens() {
+		let futural = test_font("futural");
+		let red = SpanStyle { color: 0xFF0000, pen: 2, ..SpanStyle::default() };
+		let text = RichText::new()
+			.with("!", &futural, red)
+			.with("!", &futural, SpanStyle::default())
+			.with("!", &futural, red);
+		let placed = layout_rich(&text, &Transform::IDENTITY);
+		
+		let strokes = plot_strokes(&placed);
+		assert_eq!(strokes.iter().map(|s| (s.pen, s.glyph)).collect::<Vec<_>>(), vec![
+			(1, 1), (1, 1),
+			(2, 0), (2, 0), (2, 2), (2, 2),
+		]);
+		
+		let svg = to_svg(&placed, 1.0);
+		assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="3.5 -12.5 23 22">"#));
+		assert_eq!(svg.matches("<path").count(), 3);
+		assert_eq!(svg.matches(r##"stroke="#ff0000""##).count(), 2);
+		assert_eq!(svg.matches(r##"stroke="#000000""##).count(), 1);
+		assert!(svg.ends_with("</svg>\n"));
+	}
+}
@@ -0,0 +1,284 @@
+//! AutoCAD shape font source (`.shp`), for compiling into `.shx`.
+//!
+//! Each shape is a list of bytes: `1` and `2` put the pen down and up, `8`
+//! moves by the `(x,y)` after it, and `9` moves by every `(x,y)` after it
+//! until a `(0,0)`. The pen starts down at the shape's origin, on the
+//! baseline at the left edge, with +y going up.
+
+use std::fmt::Write;
+
+use crate::{HersheyChar, HersheyError, HersheyFont};
+use crate::layout::LINE_HEIGHT;
+
+/// Longest a shape can be, in bytes.
+const MAX_SHAPE_BYTES: usize = 2000;
+
+enum Spec {
+	Code(u8),
+	Move(i8, i8),
+}
+
+impl Spec {
+	fn len(&self) -> usize {
+		match self {
+			Spec::Code(_) => 1,
+			Spec::Move(..) => 2,
+		}
+	}
+}
+
+/// Break a move up into steps that each fit in a byte.
+fn steps(dx: i32, dy: i32) -> Vec<(i8, i8)> {
+	let n = (dx.abs().max(dy.abs()) + 126) / 127;
+	(1..=n).map(|i| {
+		// whole steps, with the rounding spread out so it still adds up
+		let x = dx * i / n - dx * (i - 1) / n;
+		let y = dy * i / n - dy * (i - 1) / n;
+		(x as i8, y as i8)
+	}).collect()
+}
+
+/// One displacement code for a run of moves: `8` if it's just one, `9` if not.
+fn moves(out: &mut Vec<Spec>, moves: &[(i8, i8)]) {
+	match moves {
+		[] => {}
+		[(x, y)] => {
+			out.push(Spec::Code(8));
+			out.push(Spec::Move(*x, *y));
+		}
+		_ => {
+			out.push(Spec::Code(9));
+			out.extend(moves.iter().map(|&(x, y)| Spec::Move(x, y)));
+			out.push(Spec::Move(0, 0));
+		}
+	}
+}
+
+/// Bytes for one glyph, ending with the pen up at the next glyph's origin.
+fn shape(chr: &HersheyChar, baseline: i8) -> Vec<Spec> {
+	let to_shp = |(x, y): (f64, f64)| ((x - chr.left_hand as f64) as i32, baseline as i32 - y as i32);
+	
+	let mut out = vec![Spec::Code(2)];
+	let mut pen = (0, 0);
+	
+	for stroke in chr.strokes() {
+		let start = to_shp(stroke[0]);
+		moves(&mut out, &steps(start.0 - pen.0, start.1 - pen.1));
+		pen = start;
+		
+		let mut drawn = Vec::new();
+		for &p in &stroke[1..] {
+			let p = to_shp(p);
+			if p != pen {
+				drawn.extend(steps(p.0 - pen.0, p.1 - pen.1));
+				pen = p;
+			}
+		}
+		
+		out.push(Spec::Code(1));
+		moves(&mut out, &drawn);
+		out.push(Spec::Code(2));
+	}
+	
+	moves(&mut out, &steps(chr.advance() - pen.0, -pen.1));
+	out.push(Spec::Code(0));
+	out
+}
+
+fn write_shape(shp: &mut String, number: u32, name: &str, specs: &[Spec]) -> Result<(), HersheyError> {
+	let len = specs.iter().map(Spec::len).sum::<usize>();
+	if len > MAX_SHAPE_BYTES { return Err(HersheyError::OutOfRange); }
+	
+	let bytes = specs.iter()
+		.map(|s| match s {
+			Spec::Code(c) => c.to_string(),
+			Spec::Move(x, y) => format!("({},{})", x, y),
+		})
+		.collect::<Vec<_>>();
+	
+	writeln!(shp, "*0{:04X},{},{}", number, len, name).unwrap();
+	writeln!(shp, "{}", bytes.join(",")).unwrap();
+	Ok(())
+}
+
+/// Write `font` as shape font source, with a shape for everything in its
+/// [`char_map`](HersheyFont::char_map). `baseline` is where the font's
+/// baseline is, in Hershey units.
+///
+/// It's a plain shape font if every character fits in a byte, and a
+/// Unicode one if not. Shape numbers only go up to `FFFF`, so anything
+/// past that is left out. Fails with [`HersheyError::OutOfRange`] if a
+/// shape comes out longer than AutoCAD allows.
+///
+/// There's no [`Transform`](crate::transform::Transform) option like the
+/// other exporters have: shapes are drawn in whole Hershey units, so a
+/// rotated or skewed glyph would get snapped back onto the grid and come
+/// out wobbly. AutoCAD can scale, rotate and oblique text itself anyway.
+pub fn to_shp(font: &HersheyFont, baseline: i8) -> Result<String, HersheyError> {
+	let mut map = font.char_map();
+	map.retain(|&(c, _)| c as u32 <= 0xFFFF);
+	
+	let top = font.glyph_for('H')
+		.or_else(|| font.chars.first())
+		.and_then(|chr| chr.vertex_data.iter().flatten().map(|v| v.1).min())
+		.map_or(0, |y| baseline as i32 - y as i32);
+	let bottom = map.iter()
+		.flat_map(|&(_, i)| font.chars[i].vertex_data.iter().flatten())
+		.map(|v| v.1 as i32 - baseline as i32)
+		.max()
+		.unwrap_or(0)
+		.max(0);
+	
+	let name = font.name.replace(',', " ");
+	let mut shp = String::new();
+	if map.iter().all(|&(c, _)| (c as u32) < 0x100) {
+		writeln!(shp, "*0,4,{}", name).unwrap();
+		writeln!(shp, "{},{},0,0", top, bottom).unwrap();
+	} else {
+		writeln!(shp, "*UNIFONT,6,{}", name).unwrap();
+		writeln!(shp, "{},{},0,0,0,0", top, bottom).unwrap();
+	}
+	
+	// down a line, for multi-line text
+	let mut line_feed = vec![Spec::Code(2)];
+	moves(&mut line_feed, &steps(0, -(LINE_HEIGHT as i32)));
+	line_feed.push(Spec::Code(0));
+	writeln!(shp).unwrap();
+	write_shape(&mut shp, 10, "lf", &line_feed)?;
+	
+	for (c, i) in map {
+		writeln!(shp).unwrap();
+		// lowercase names don't take up memory in AutoCAD
+		write_shape(&mut shp, c as u32, &format!("u{:04x}", c as u32), &shape(&font.chars[i], baseline))?;
+	}
+	
+	Ok(shp)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	use crate::Point;
+	
+	type Pos = (i32, i32);
+	
+	/// Run a shape's bytes like AutoCAD would, giving the strokes and where the pen ends up.
+	fn draw(bytes: &str) -> (Vec<Vec<Pos>>, Pos) {
+		let nums = bytes.split(',')
+			.map(|b| b.trim_matches(|c| c == '(' || c == ')').parse::<i32>().unwrap())
+			.collect::<Vec<_>>();
+		
+		let mut strokes: Vec<Vec<Pos>> = Vec::new();
+		let (mut pen, mut down) = ((0, 0), true);
+		let mut i = 0;
+		
+		while nums[i] != 0 {
+			let mut moves = Vec::new();
+			match nums[i] {
+				1 => {
+					down = true;
+					strokes.push(vec![pen]);
+				}
+				2 => down = false,
+				8 => {
+					moves.push((nums[i + 1], nums[i + 2]));
+					i += 2;
+				}
+				9 => {
+					while (nums[i + 1], nums[i + 2]) != (0, 0) {
+						moves.push((nums[i + 1], nums[i + 2]));
+						i += 2;
+					}
+					i += 2;
+				}
+				b => panic!("unexpected {}", b),
+			}
+			i += 1;
+			
+			for (dx, dy) in moves {
+				pen = (pen.0 + dx, pen.1 + dy);
+				if down { strokes.last_mut().unwrap().push(pen); }
+			}
+		}
+		
+		(strokes, pen)
+	}
+	
+	fn shape_bytes<'a>(shp: &'a str, header: &str) -> &'a str {
+		let at = shp.find(header).unwrap();
+		shp[at..].lines().nth(1).unwrap()
+	}
+	
+	#[test]
+	fn header_and_dash() -> Result<(), HersheyError> {
+		let shp = to_shp(&test_font("futural"), 9)?;
+		
+		assert!(shp.starts_with("*0,4,futural\n21,7,0,0\n"));
+		assert!(shp.contains("\n*0000A,5,lf\n2,8,(0,-32),0\n"));
+		assert!(shp.contains("\n*0002D,13,u002d\n2,8,(4,9),1,8,(18,0),2,8,(4,-9),0\n"));
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn draws_the_same() -> Result<(), HersheyError> {
+		let font = test_font("futural");
+		let shp = to_shp(&font, 9)?;
+		
+		for (c, i) in font.char_map() {
+			let chr = &font.chars[i];
+			let (strokes, pen) = draw(shape_bytes(&shp, &format!("*0{:04X},", c as u32)));
+			
+			let expected = chr.strokes().iter()
+				.map(|s| {
+					let mut s = s.iter()
+						.map(|&(x, y): &Point| (x as i32 - chr.left_hand as i32, 9 - y as i32))
+						.collect::<Vec<_>>();
+					s.dedup();
+					s
+				})
+				.collect::<Vec<_>>();
+			
+			assert_eq!(strokes, expected, "{:?}", c);
+			assert_eq!(pen, (chr.advance(), 0));
+		}
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn long_moves_get_split() -> Result<(), HersheyError> {
+		let tall = HersheyChar {
+			id: 12345,
+			vertex_num: 2,
+			left_hand: -8,
+			right_hand: 8,
+			vertex_data: vec![Some((0, -120)), Some((0, 100))],
+		};
+		let shp = to_shp(&HersheyFont::new("tall", vec![tall]), 9)?;
+		let bytes = shape_bytes(&shp, "*00020,");
+		
+		assert_eq!(bytes, "2,9,(4,64),(4,65),(0,0),1,9,(0,-110),(0,-110),(0,0),2,8,(8,91),0");
+		assert_eq!(draw(bytes).1, (16, 0));
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn skips_past_ffff() -> Result<(), HersheyError> {
+		let font = test_font("futural");
+		let font = HersheyFont {
+			unicode: Some(vec![Some('A'), Some('𝄞')]),
+			..HersheyFont::new("clef", font.chars[..2].to_vec())
+		};
+		let shp = to_shp(&font, 9)?;
+		
+		// only the A is left, so it doesn't even need to be a Unicode font
+		assert!(shp.starts_with("*0,4,clef\n"));
+		assert!(shp.contains("\n*00041,"));
+		assert_eq!(shp.matches("\n*0").count(), 2);
+		
+		Ok(())
+	}
+}
@@ -0,0 +1,133 @@
+//! Falling back to other fonts for characters the first one doesn't have.
+//! Latin text with the odd α or ≤ in it can get its Latin from one font,
+//! and the rest from greek.jhf and mathlow.jhf.
+
+use crate::{HersheyChar, HersheyFont};
+use crate::layout::Glyphs;
+
+impl HersheyFont {
+	/// How tall the glyphs usually are, in font units: the median height of
+	/// the ones that draw anything. Most of the bundled fonts come out at 21.
+	pub fn typical_height(&self) -> f64 {
+		let mut heights: Vec<i32> = self.chars.iter()
+			.filter_map(|chr| {
+				let ys = chr.vertex_data.iter().flatten().map(|&(_, y)| y as i32);
+				Some(ys.clone().max()? - ys.min()?)
+			})
+			.collect();
+		
+		if heights.is_empty() { return 0.0; }
+		
+		heights.sort_unstable();
+		heights[heights.len() / 2] as f64
+	}
+}
+
+/// A list of fonts to look through in order. The first one that has a
+/// character gets to draw it.
+///
+/// Fonts that aren't really in ASCII order need their
+/// [`unicode`](HersheyFont::unicode) map, or they'll claim to have plain
+/// letters (and nothing else). See
+/// [`with_builtin_unicode`](HersheyFont::with_builtin_unicode).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontStack<'a> {
+	/// Each font, and how much to scale its glyphs by.
+	fonts: Vec<(&'a HersheyFont, f64)>,
+}
+
+impl<'a> FontStack<'a> {
+	/// Just `primary`, for now. The fonts after it get sized to match it.
+	pub fn new(primary: &'a HersheyFont) -> Self {
+		FontStack { fonts: vec![(primary, 1.0)] }
+	}
+	
+	/// Add a font to try after the rest, scaled so its
+	/// [`typical_height`](HersheyFont::typical_height) matches the first font's.
+	pub fn then(self, font: &'a HersheyFont) -> Self {
+		let height = font.typical_height();
+		let scale = if height > 0.0 { self.fonts[0].0.typical_height() / height } else { 1.0 };
+		self.then_scaled(font, scale)
+	}
+	
+	/// Same as [`then`](Self::then), but with the scale picked by hand.
+	pub fn then_scaled(mut self, font: &'a HersheyFont, scale: f64) -> Self {
+		self.fonts.push((font, scale));
+		self
+	}
+	
+	/// The fonts, in order, with their scales.
+	pub fn fonts(&self) -> &[(&'a HersheyFont, f64)] {
+		&self.fonts
+	}
+	
+	/// The first font with a glyph for `c`, and its scale.
+	pub fn font_for(&self, c: char) -> Option<(&'a HersheyFont, f64)> {
+		self.fonts.iter()
+			.find(|(font, _)| font.glyph_for(c).is_some())
+			.copied()
+	}
+	
+	/// Every character in `text` that none of the fonts have, once each, in
+	/// the order they first show up. `\n` doesn't count, since layout
+	/// handles it.
+	pub fn missing(&self, text: &str) -> Vec<char> {
+		let mut missing = Vec::new();
+		for c in text.chars() {
+			if c != '\n' && !missing.contains(&c) && self.font_for(c).is_none() {
+				missing.push(c);
+			}
+		}
+		missing
+	}
+}
+
+impl Glyphs for FontStack<'_> {
+	fn sized_glyph(&self, c: char) -> Option<(&HersheyChar, f64)> {
+		self.fonts.iter()
+			.find_map(|&(font, scale)| Some((font.glyph_for(c)?, scale)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	use crate::layout::{layout_str, line_width};
+	use crate::transform::Transform;
+	
+	#[test]
+	fn falls_back() {
+		let (futural, greek, mathlow, mut markers) = (test_font("futural").with_builtin_unicode(), test_font("greek").with_builtin_unicode(), test_font("mathlow").with_builtin_unicode(), test_font("markers").with_builtin_unicode());
+		markers.unicode = Some((' '..='~')
+			.map(|c| crate::symbols::MARKERS.iter().find(|s| s.key == c)?.unicode)
+			.collect());
+		assert_eq!(futural.typical_height(), 21.0);
+		
+		let stack = FontStack::new(&futural).then(&greek).then(&mathlow).then(&markers);
+		assert_eq!(stack.fonts()[1].1, 1.0);
+		assert_eq!(stack.fonts()[3].1, 21.0 / markers.typical_height());
+		
+		// the Latin a wins over mathlow's
+		assert_eq!(stack.font_for('a').unwrap().0.name, "futural");
+		assert_eq!(stack.font_for('α').unwrap().0.name, "greek");
+		assert_eq!(stack.font_for('≤').unwrap().0.name, "mathlow");
+		assert_eq!(stack.missing("aé≤\nбé"), vec!['é', 'б']);
+		
+		let placed = layout_str(&stack, "aα≤é", &Transform::IDENTITY);
+		assert_eq!(placed.len(), 3);
+		assert_eq!(placed[1].chr, greek.glyph_for('α').unwrap());
+		assert_eq!(placed[2].index, 2);
+		
+		let width = line_width(&futural, "a") + line_width(&greek, "α") + line_width(&mathlow, "≤");
+		assert_eq!(line_width(&stack, "aα≤é"), width);
+		
+		// markers get blown up to match
+		let square = markers.glyph_for('□').unwrap();
+		let scale = stack.fonts()[3].1;
+		let expected = Transform::translate(-square.left_hand as f64, 0.0).then(&Transform::uniform_scale(scale));
+		let placed = layout_str(&stack, "□", &Transform::IDENTITY);
+		assert_eq!(placed[0].strokes(), square.transformed(&expected));
+		assert_eq!(line_width(&stack, "□"), square.advance() as f64 * scale);
+	}
+}
@@ -0,0 +1,659 @@
+//! SVG fonts, the single-line kind that Inkscape's Hershey Text extension
+//! and the AxiDraw tools use: every `<glyph>` is a stroke-only path.
+//!
+//! SVG fonts have +y going up from the baseline, and each glyph's origin
+//! on its left edge. Hershey glyphs are centred on theirs, so going either
+//! way shifts things over by [`HersheyChar::left_hand`].
+
+use std::fmt::Write;
+
+use crate::{HersheyChar, HersheyError, HersheyFont, Point};
+use crate::bezier::num;
+use crate::layout::LINE_HEIGHT;
+use crate::transform::Transform;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvgFontOptions {
+	/// How many font units one [`LINE_HEIGHT`] becomes.
+	pub units_per_em: f64,
+	
+	/// Where the baseline is, in Hershey units. It's 9 for the bundled fonts.
+	pub baseline: f64,
+	
+	/// Done to every glyph first, in Hershey units around its origin, when
+	/// writing. Widths follow whatever it does to x. Reading ignores it.
+	pub transform: Transform,
+}
+
+impl Default for SvgFontOptions {
+	fn default() -> Self {
+		SvgFontOptions {
+			units_per_em: 1000.0,
+			baseline: 9.0,
+			transform: Transform::IDENTITY,
+		}
+	}
+}
+
+impl SvgFontOptions {
+	fn scale(&self) -> f64 {
+		self.units_per_em / LINE_HEIGHT
+	}
+	
+	/// Hershey coordinates for `chr` to font units.
+	fn transform(&self, chr: &HersheyChar) -> Transform {
+		let s = self.scale();
+		let (left, _) = chr.transformed_edges(&self.transform);
+		self.transform
+			.then(&Transform::translate(-left, -self.baseline))
+			.then(&Transform::scale(s, -s))
+	}
+	
+	/// How far along the next glyph starts, in font units.
+	fn advance(&self, chr: &HersheyChar) -> f64 {
+		let (left, right) = chr.transformed_edges(&self.transform);
+		(right - left) * self.scale()
+	}
+}
+
+/// Make `s` safe to put in an attribute.
+fn escape(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'&' => out.push_str("&amp;"),
+			'<' => out.push_str("&lt;"),
+			'>' => out.push_str("&gt;"),
+			'"' => out.push_str("&quot;"),
+			'\'' => out.push_str("&apos;"),
+			c => out.push(c),
+		}
+	}
+	out
+}
+
+/// Path data for one glyph, all `M`s and `L`s. Dots get a zero-length line
+/// so a plotter still puts the pen down for them.
+fn glyph_path(chr: &HersheyChar, options: &SvgFontOptions) -> String {
+	let mut d = String::new();
+	
+	for stroke in chr.transformed(&options.transform(chr)) {
+		for (i, p) in stroke.iter().enumerate() {
+			if !d.is_empty() { d.push(' '); }
+			let command = if i == 0 { 'M' } else { 'L' };
+			write!(d, "{}{} {}", command, num(p.0), num(p.1)).unwrap();
+		}
+		if let [p] = stroke[..] {
+			write!(d, " L{} {}", num(p.0), num(p.1)).unwrap();
+		}
+	}
+	
+	d
+}
+
+/// Write `font` as an SVG font, with a `<glyph>` for everything in its
+/// [`char_map`](HersheyFont::char_map).
+pub fn to_svg_font(font: &HersheyFont, options: &SvgFontOptions) -> String {
+	let scale = options.scale();
+	let units = |v: f64| num(v * scale);
+	
+	// tops of `H` and `x`, for the cap and x heights
+	let top_of = |c: char| font.glyph_for(c)
+		.and_then(|chr| chr.transformed(&options.transform).into_iter().flatten().map(|p| p.1).reduce(f64::min))
+		.map_or(0.0, |y| units(options.baseline - y));
+	
+	let (mut x_min, mut y_min, mut x_max, mut y_max) = (0.0f64, 0.0f64, 0.0f64, 0.0f64);
+	for chr in &font.chars {
+		for stroke in chr.transformed(&options.transform(chr)) {
+			for (x, y) in stroke {
+				x_min = x_min.min(x);
+				y_min = y_min.min(y);
+				x_max = x_max.max(x);
+				y_max = y_max.max(y);
+			}
+		}
+	}
+	
+	let default_advance = font.glyph_for(' ').map_or(0.0, |chr| num(options.advance(chr)));
+	let id = font.name.chars()
+		.map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+		.collect::<String>();
+	
+	let mut svg = String::new();
+	svg.push_str("<?xml version=\"1.0\" standalone=\"no\"?>\n");
+	svg.push_str("<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\">\n");
+	svg.push_str("<defs>\n");
+	writeln!(svg, "<font id=\"{}\" horiz-adv-x=\"{}\">", escape(&id), default_advance).unwrap();
+	writeln!(svg,
+		"<font-face font-family=\"{}\" units-per-em=\"{}\" ascent=\"{}\" descent=\"{}\" cap-height=\"{}\" x-height=\"{}\" bbox=\"{} {} {} {}\"/>",
+		escape(&font.name),
+		num(options.units_per_em),
+		units(options.baseline + LINE_HEIGHT / 2.0),
+		units(options.baseline - LINE_HEIGHT / 2.0),
+		top_of('H'),
+		top_of('x'),
+		num(x_min), num(y_min), num(x_max), num(y_max),
+	).unwrap();
+	writeln!(svg, "<missing-glyph horiz-adv-x=\"{}\"/>", default_advance).unwrap();
+	
+	for (c, i) in font.char_map() {
+		let chr = &font.chars[i];
+		write!(svg, "<glyph unicode=\"{}\" horiz-adv-x=\"{}\"", escape(&c.to_string()), num(options.advance(chr))).unwrap();
+		
+		let d = glyph_path(chr, options);
+		if !d.is_empty() {
+			write!(svg, " d=\"{}\"", d).unwrap();
+		}
+		svg.push_str("/>\n");
+	}
+	
+	svg.push_str("</font>\n");
+	svg.push_str("</defs>\n");
+	svg.push_str("</svg>\n");
+	svg
+}
+
+/// Something [`from_svg_font`] had to fudge. `glyph` is an index into the
+/// font's [`chars`](HersheyFont::chars).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SvgWarning {
+	/// Some of the glyph was too big for the Hershey grid, and got squashed
+	/// onto its edge.
+	OutOfRange { glyph: usize },
+	/// The glyph fit, but some of it is outside the smaller range a .jhf
+	/// file can hold (-50 to 44, and not `(-50, 0)`), so
+	/// [`to_jhf`](HersheyChar::to_jhf) won't write it.
+	NotJhf { glyph: usize },
+	/// The glyph's `unicode` is more than one character (a ligature,
+	/// probably), so it's in the font but nothing maps to it.
+	Ligature { glyph: usize, unicode: String },
+}
+
+/// Undo [`escape`], plus numeric character references.
+fn unescape(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	let mut rest = s;
+	
+	while let Some(at) = rest.find('&') {
+		out.push_str(&rest[..at]);
+		rest = &rest[at..];
+		
+		let end = match rest.find(';') {
+			Some(end) => end,
+			None => break,
+		};
+		let decoded = match &rest[1..end] {
+			"amp" => Some('&'),
+			"lt" => Some('<'),
+			"gt" => Some('>'),
+			"quot" => Some('"'),
+			"apos" => Some('\''),
+			e if e.starts_with("#x") => u32::from_str_radix(&e[2..], 16).ok().and_then(char::from_u32),
+			e if e.starts_with('#') => e[1..].parse().ok().and_then(char::from_u32),
+			_ => None,
+		};
+		
+		match decoded {
+			Some(c) => {
+				out.push(c);
+				rest = &rest[end + 1..];
+			}
+			None => {
+				out.push('&');
+				rest = &rest[1..];
+			}
+		}
+	}
+	
+	out.push_str(rest);
+	out
+}
+
+/// Every opening (or self-closing) tag in `xml`, as its name and attributes.
+/// Not a real XML parser, but SVG fonts are pretty tame.
+fn tags(xml: &str) -> Vec<(&str, Vec<(&str, String)>)> {
+	let mut tags = Vec::new();
+	let mut rest = xml;
+	
+	while let Some(at) = rest.find('<') {
+		rest = &rest[at + 1..];
+		
+		// comments and CDATA can have `>`s in them
+		let skip_to = if rest.starts_with("!--") {
+			"-->"
+		} else if rest.starts_with("![CDATA[") {
+			"]]>"
+		} else {
+			""
+		};
+		if !skip_to.is_empty() {
+			rest = rest.find(skip_to).map_or("", |end| &rest[end + skip_to.len()..]);
+			continue;
+		}
+		
+		let name_end = rest.find(|c: char| c.is_whitespace() || c == '/' || c == '>').unwrap_or(rest.len());
+		let name = &rest[..name_end];
+		rest = &rest[name_end..];
+		
+		let mut attrs = Vec::new();
+		loop {
+			rest = rest.trim_start();
+			if rest.is_empty() || rest.starts_with('>') || rest.starts_with("/>") {
+				break;
+			}
+			
+			let eq = match rest.find('=') {
+				Some(eq) => eq,
+				None => break,
+			};
+			let key = rest[..eq].trim();
+			rest = rest[eq + 1..].trim_start();
+			
+			let quote = match rest.chars().next() {
+				Some(q) if q == '"' || q == '\'' => q,
+				_ => break,
+			};
+			let end = match rest[1..].find(quote) {
+				Some(end) => end + 1,
+				None => break,
+			};
+			attrs.push((key, unescape(&rest[1..end])));
+			rest = &rest[end + 1..];
+		}
+		
+		if !name.starts_with(['/', '?', '!']) {
+			tags.push((name, attrs));
+		}
+	}
+	
+	tags
+}
+
+fn attr<'a>(attrs: &'a [(&str, String)], key: &str) -> Option<&'a str> {
+	attrs.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str())
+}
+
+/// Path data, split up into command letters and numbers.
+enum Token {
+	Command(char),
+	Number(f64),
+}
+
+fn tokenize(d: &str) -> Result<Vec<Token>, HersheyError> {
+	let mut tokens = Vec::new();
+	let chars = d.char_indices().collect::<Vec<_>>();
+	let mut i = 0;
+	
+	while i < chars.len() {
+		let (at, c) = chars[i];
+		
+		if c.is_whitespace() || c == ',' {
+			i += 1;
+		} else if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+			tokens.push(Token::Command(c));
+			i += 1;
+		} else {
+			// a number goes until something that can't be part of it, like a
+			// second `.` or a sign that isn't right after an exponent
+			let mut end = i;
+			let (mut dot, mut exp) = (false, false);
+			while end < chars.len() {
+				let c = chars[end].1;
+				let ok = match c {
+					'0'..='9' => true,
+					'+' | '-' => end == i || matches!(chars[end - 1].1, 'e' | 'E'),
+					'.' if !dot && !exp => { dot = true; true }
+					'e' | 'E' if !exp && end > i => { exp = true; true }
+					_ => false,
+				};
+				if !ok { break; }
+				end += 1;
+			}
+			
+			let to = chars.get(end).map_or(d.len(), |c| c.0);
+			let n: f64 = d[at..to].parse().map_err(|_| HersheyError::InvalidSvgPath)?;
+			// things like 1e309 parse, as infinity
+			if !n.is_finite() { return Err(HersheyError::InvalidSvgPath); }
+			tokens.push(Token::Number(n));
+			i = end.max(i + 1);
+		}
+	}
+	
+	Ok(tokens)
+}
+
+/// Points along a quadratic or cubic Bézier, not counting the start.
+fn flatten(controls: &[Point], scale: f64) -> Vec<Point> {
+	// about one point every couple of Hershey units is plenty, since it's
+	// all getting rounded to the grid anyway
+	let hull = controls.windows(2)
+		.map(|pair| (pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1))
+		.sum::<f64>();
+	let steps = (hull / scale / 2.0).ceil();
+	// NaN fails this too, and there has to be at least one step
+	let steps = if steps >= 1.0 { steps.min(64.0) as usize } else { 1 };
+	
+	(1..=steps).map(|i| {
+		let t = i as f64 / steps as f64;
+		// de Casteljau
+		let mut pts = controls.to_vec();
+		while pts.len() > 1 {
+			pts = pts.windows(2)
+				.map(|pair| (pair[0].0 + (pair[1].0 - pair[0].0) * t, pair[0].1 + (pair[1].1 - pair[0].1) * t))
+				.collect();
+		}
+		pts[0]
+	}).collect()
+}
+
+/// Flatten path data into polylines, in the path's own units. `scale` is
+/// how many of those make a Hershey unit, for deciding how finely to cut curves.
+fn path_polylines(d: &str, scale: f64) -> Result<Vec<Vec<Point>>, HersheyError> {
+	let tokens = tokenize(d)?;
+	let mut lines: Vec<Vec<Point>> = Vec::new();
+	let (mut cur, mut start) = ((0.0, 0.0), (0.0, 0.0));
+	let mut command = None;
+	let mut i = 0;
+	
+	let number = |i: &mut usize| match tokens.get(*i) {
+		Some(Token::Number(n)) => { *i += 1; Ok(*n) }
+		_ => Err(HersheyError::InvalidSvgPath),
+	};
+	
+	while i < tokens.len() {
+		let c = match tokens[i] {
+			Token::Command(c) => { i += 1; c }
+			// more numbers, so the last command goes again
+			Token::Number(_) => command.ok_or(HersheyError::InvalidSvgPath)?,
+		};
+		let relative = c.is_ascii_lowercase();
+		let close = c.eq_ignore_ascii_case(&'z');
+		let origin = if relative { cur } else { (0.0, 0.0) };
+		let point = |i: &mut usize| -> Result<Point, HersheyError> {
+			Ok((origin.0 + number(i)?, origin.1 + number(i)?))
+		};
+		
+		let to = match c.to_ascii_uppercase() {
+			'M' => {
+				cur = point(&mut i)?;
+				start = cur;
+				lines.push(vec![cur]);
+				// anything after the first pair is a line
+				command = Some(if relative { 'l' } else { 'L' });
+				continue;
+			}
+			'Z' => {
+				command = None;
+				vec![start]
+			}
+			'L' => vec![point(&mut i)?],
+			'H' => vec![(origin.0 + number(&mut i)?, cur.1)],
+			'V' => vec![(cur.0, origin.1 + number(&mut i)?)],
+			'C' => {
+				let (c1, c2, end) = (point(&mut i)?, point(&mut i)?, point(&mut i)?);
+				flatten(&[cur, c1, c2, end], scale)
+			}
+			'Q' => {
+				let (c1, end) = (point(&mut i)?, point(&mut i)?);
+				flatten(&[cur, c1, end], scale)
+			}
+			_ => return Err(HersheyError::InvalidSvgPath),
+		};
+		
+		if !close {
+			command = Some(c);
+		}
+		
+		match lines.last_mut() {
+			Some(line) => line.extend(&to),
+			None => return Err(HersheyError::InvalidSvgPath),
+		}
+		cur = to[to.len() - 1];
+		
+		// drawing after a Z starts a new subpath from the same spot
+		if close {
+			lines.push(vec![cur]);
+		}
+	}
+	
+	lines.retain(|line| line.len() > 1);
+	Ok(lines)
+}
+
+/// Clamp to what fits in an `i8`, and say whether it had to.
+fn fit(v: f64) -> (i8, bool) {
+	let r = v.round();
+	(r.clamp(i8::MIN as f64, i8::MAX as f64) as i8, !(i8::MIN as f64..=i8::MAX as f64).contains(&r))
+}
+
+/// Read an SVG font (the first `<font>` in `svg`) onto the Hershey grid.
+/// Curves get flattened, and everything gets rounded to whole Hershey units.
+///
+/// The file's own `units-per-em` wins over the one in `options`. Glyphs are
+/// centred on their origin like Hershey glyphs are, and come out in file
+/// order with a [`unicode`](HersheyFont::unicode) table. Fails with
+/// [`HersheyError::InvalidUnitsPerEm`] if the one it ends up with isn't a
+/// positive number.
+pub fn from_svg_font(svg: &str, options: &SvgFontOptions) -> Result<(HersheyFont, Vec<SvgWarning>), HersheyError> {
+	let tags = tags(svg);
+	
+	let font_at = tags.iter().position(|(name, _)| *name == "font").ok_or(HersheyError::NoSvgFont)?;
+	let font_attrs = &tags[font_at].1;
+	let default_advance = attr(font_attrs, "horiz-adv-x").and_then(|v| v.trim().parse().ok()).unwrap_or(0.0);
+	
+	let face = tags[font_at..].iter().find(|(name, _)| *name == "font-face").map(|(_, a)| a.as_slice()).unwrap_or(&[]);
+	let units_per_em = attr(face, "units-per-em")
+		.and_then(|v| v.trim().parse().ok())
+		.unwrap_or(options.units_per_em);
+	if !(units_per_em.is_finite() && units_per_em > 0.0) {
+		return Err(HersheyError::InvalidUnitsPerEm);
+	}
+	let options = SvgFontOptions { units_per_em, ..*options };
+	let scale = options.scale();
+	
+	let name = attr(face, "font-family")
+		.or_else(|| attr(font_attrs, "id"))
+		.unwrap_or_default();
+	
+	let mut chars = Vec::new();
+	let mut unicode = Vec::new();
+	let mut warnings = Vec::new();
+	
+	// only this font's glyphs, not any other font's later on
+	let glyphs = tags[font_at + 1..].iter()
+		.take_while(|(name, _)| *name != "font")
+		.filter(|(name, _)| *name == "glyph");
+	for (_, attrs) in glyphs {
+		let glyph = chars.len();
+		let mut out_of_range = false;
+		
+		let advance = attr(attrs, "horiz-adv-x").and_then(|v| v.trim().parse().ok()).unwrap_or(default_advance);
+		let (advance, clamped) = fit(advance / scale);
+		out_of_range |= clamped;
+		let left_hand = -(advance / 2);
+		let (right_hand, clamped) = fit(left_hand as f64 + advance as f64);
+		out_of_range |= clamped;
+		
+		let mut vertex_data = Vec::new();
+		for line in path_polylines(attr(attrs, "d").unwrap_or_default(), scale)? {
+			if !vertex_data.is_empty() { vertex_data.push(None); }
+			
+			let mut run: Vec<(i8, i8)> = Vec::new();
+			for (x, y) in line {
+				let (x, clamped_x) = fit(x / scale + left_hand as f64);
+				let (y, clamped_y) = fit(options.baseline - y / scale);
+				out_of_range |= clamped_x || clamped_y;
+				run.push((x, y));
+			}
+			run.dedup();
+			vertex_data.extend(run.into_iter().map(Some));
+		}
+		
+		let chr = HersheyChar {
+			id: 12345, // like the bundled fonts
+			vertex_num: vertex_data.len(),
+			left_hand, right_hand,
+			vertex_data,
+		};
+		
+		if out_of_range {
+			warnings.push(SvgWarning::OutOfRange { glyph });
+		} else if chr.to_jhf().is_err() {
+			warnings.push(SvgWarning::NotJhf { glyph });
+		}
+		
+		let text = attr(attrs, "unicode").unwrap_or_default();
+		let mut text_chars = text.chars();
+		unicode.push(match (text_chars.next(), text_chars.next()) {
+			(Some(c), None) => Some(c),
+			(None, _) => None,
+			_ => {
+				warnings.push(SvgWarning::Ligature { glyph, unicode: text.to_string() });
+				None
+			}
+		});
+		
+		chars.push(chr);
+	}
+	
+	if chars.is_empty() {
+		return Err(HersheyError::NoSvgFont);
+	}
+	
+	let font = HersheyFont {
+		unicode: Some(unicode),
+		..HersheyFont::new(name, chars)
+	};
+	Ok((font, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	
+	#[test]
+	fn glyphs_and_metrics() {
+		let font = test_font("futural");
+		let svg = to_svg_font(&font, &SvgFontOptions::default());
+		
+		assert_eq!(svg.matches("<glyph ").count(), font.char_map().len());
+		assert!(svg.contains("<font id=\"futural\" horiz-adv-x=\"500\">"));
+		assert!(svg.contains("units-per-em=\"1000\" ascent=\"781.25\" descent=\"-218.75\" cap-height=\"656.25\" x-height=\"437.5\""));
+		
+		assert!(svg.contains("<glyph unicode=\" \" horiz-adv-x=\"500\"/>"));
+		assert!(svg.contains("<glyph unicode=\"-\" horiz-adv-x=\"812.5\" d=\"M125 281.25 L687.5 281.25\"/>"));
+	}
+	
+	#[test]
+	fn escapes_and_dots() {
+		let font = test_font("futural");
+		let svg = to_svg_font(&font, &SvgFontOptions::default());
+		
+		assert!(svg.contains("unicode=\"&amp;\""));
+		assert!(svg.contains("unicode=\"&lt;\""));
+		assert!(svg.contains("unicode=\"&quot;\""));
+		
+		let dot = HersheyChar::new_from_str("12345  2JZRR").unwrap();
+		assert_eq!(glyph_path(&dot, &SvgFontOptions::default()), "M250 281.25 L250 281.25");
+	}
+	
+	#[test]
+	fn transformed() {
+		let font = test_font("futural");
+		let transform = Transform::mirror_x().then(&Transform::scale(2.0, 1.0));
+		let svg = to_svg_font(&font, &SvgFontOptions { transform, ..SvgFontOptions::default() });
+		
+		// twice as wide, and drawn right to left
+		assert!(svg.contains("<font id=\"futural\" horiz-adv-x=\"1000\">"));
+		assert!(svg.contains("<glyph unicode=\"-\" horiz-adv-x=\"1625\" d=\"M1375 281.25 L250 281.25\"/>"));
+	}
+	
+	#[test]
+	fn round_trip() -> Result<(), HersheyError> {
+		let font = test_font("futural");
+		let (back, warnings) = from_svg_font(&to_svg_font(&font, &SvgFontOptions::default()), &SvgFontOptions::default())?;
+		
+		assert!(warnings.is_empty());
+		assert_eq!(back.name, "futural");
+		assert_eq!(back.char_map(), font.char_map());
+		
+		// same shapes and advances, just possibly shifted over
+		for (a, b) in font.chars.iter().zip(&back.chars) {
+			let shift = b.left_hand - a.left_hand;
+			assert_eq!(a.advance(), b.advance());
+			assert_eq!(
+				a.vertex_data.iter().map(|v| v.map(|(x, y)| (x + shift, y))).collect::<Vec<_>>(),
+				b.vertex_data,
+			);
+		}
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn path_commands() -> Result<(), HersheyError> {
+		// a box with relative lines, then a curve that should get flattened
+		let lines = path_polylines("M0,0 h10 v10 H0 z m5 5 l1-1 Q 10 0 20 10 C20 20 30 20 30 10", 1.0)?;
+		
+		assert_eq!(lines.len(), 2);
+		assert_eq!(lines[0], vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 0.0)]);
+		assert_eq!(lines[1][..2], [(5.0, 5.0), (6.0, 4.0)]);
+		assert_eq!(lines[1].last(), Some(&(30.0, 10.0)));
+		assert!(lines[1].len() > 6);
+		
+		assert_eq!(path_polylines("M0 0 A 1 1 0 0 0 5 5", 1.0), Err(HersheyError::InvalidSvgPath));
+		assert_eq!(path_polylines("M0 0 L 5", 1.0), Err(HersheyError::InvalidSvgPath));
+		
+		Ok(())
+	}
+	
+	#[test]
+	fn import_warnings() -> Result<(), HersheyError> {
+		const SVG: &str = r#"<svg><!-- <font> in a comment --><defs>
+			<font id="test" horiz-adv-x="16"><font-face units-per-em="32" font-family="Test &amp; Co"/>
+			<glyph unicode="&#x3b1;" d="M0 0 L 8 8"/>
+			<glyph unicode="fi" horiz-adv-x="8" d="M0 0 L 1000 0"/>
+			<glyph unicode=" "/>
+			<glyph unicode="-" d="M0 0 L 60 0"/>
+			<glyph unicode="." d="M-42 9 L -42 9"/>
+			</font></defs></svg>"#;
+		
+		let (font, warnings) = from_svg_font(SVG, &SvgFontOptions::default())?;
+		
+		assert_eq!(font.name, "Test & Co");
+		assert_eq!(font.chars.len(), 5);
+		assert_eq!(font.chars[0].vertex_data, vec![Some((-8, 9)), Some((0, 1))]);
+		assert_eq!((font.chars[0].left_hand, font.chars[0].right_hand), (-8, 8));
+		assert_eq!(font.glyph_for('α'), Some(&font.chars[0]));
+		assert_eq!(font.glyph_for(' ').map(|c| c.advance()), Some(16));
+		assert_eq!(warnings, vec![
+			SvgWarning::OutOfRange { glyph: 1 },
+			SvgWarning::Ligature { glyph: 1, unicode: "fi".to_string() },
+			SvgWarning::NotJhf { glyph: 3 },
+			SvgWarning::NotJhf { glyph: 4 },
+		]);
+		assert_eq!(font.chars[4].vertex_data, vec![Some((-50, 0))]);
+		
+		assert_eq!(from_svg_font("<svg/>", &SvgFontOptions::default()), Err(HersheyError::NoSvgFont));
+		
+		Ok(())
+	}
+	
+	// These all used to panic.
+	#[test]
+	fn bad_numbers() {
+		let font = |units_per_em: &str, d: &str| from_svg_font(
+			&format!(r#"<font><font-face units-per-em="{}"/><glyph unicode="a" d="{}"/></font>"#, units_per_em, d),
+			&SvgFontOptions::default(),
+		);
+		
+		assert_eq!(font("0", "M0 0 C 0 0 0 0 0 0"), Err(HersheyError::InvalidUnitsPerEm));
+		assert_eq!(font("NaN", "M0 0 L 1 1"), Err(HersheyError::InvalidUnitsPerEm));
+		assert_eq!(font("1000", "M0 0 C 1e309 0 5 5 10 10"), Err(HersheyError::InvalidSvgPath));
+		assert!(font("1000", "M0 0 C 0 0 0 0 0 0").is_ok());
+		
+		// even with nothing sensible to go on, a curve gets somewhere
+		assert_eq!(flatten(&[(0.0, 0.0), (1.0, 1.0)], f64::NAN), vec![(1.0, 1.0)]);
+	}
+}
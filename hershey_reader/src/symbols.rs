@@ -0,0 +1,292 @@
+//! Names for what's in the symbol fonts. astrology.jhf, music.jhf and the
+//! rest put their glyphs on ASCII keys for no reason except that there
+//! were keys free, so there's no sensible [`unicode`](crate::unicode) map
+//! for them. These tables say what each one is instead:
+//! `symbols::music("treble_clef")` gets you the key, and the Unicode
+//! character too, when there is one.
+//!
+//! Only the glyphs that are clearly something are named. The fonts have
+//! plenty of odd pieces and repeats that aren't.
+
+use crate::{HersheyChar, HersheyFont};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+	/// Lowercase, with underscores, like `treble_clef`.
+	pub name: &'static str,
+	/// The ASCII character it's on in the font.
+	pub key: char,
+	/// The real character for it, if Unicode has one.
+	pub unicode: Option<char>,
+}
+
+impl Symbol {
+	/// Its glyph in `font`, which ought to be the font it came from.
+	pub fn glyph<'a>(&self, font: &'a HersheyFont) -> Option<&'a HersheyChar> {
+		font.glyph_for(self.key)
+	}
+}
+
+const fn sym(name: &'static str, key: char, unicode: Option<char>) -> Symbol {
+	Symbol { name, key, unicode }
+}
+
+pub const ASTROLOGY: &[Symbol] = &[
+	sym("sun", '#', Some('☉')),
+	sym("mercury", '$', Some('☿')),
+	sym("venus", '%', Some('♀')),
+	sym("earth", '&', Some('⊕')),
+	sym("comet", '\'', Some('☄')),
+	sym("jupiter", '*', Some('♃')),
+	sym("saturn", '+', Some('♄')),
+	sym("uranus", '-', Some('♅')),
+	sym("neptune", '/', Some('♆')),
+	sym("pluto", ':', Some('♇')),
+	sym("moon", ';', Some('☽')),
+	sym("mars", '<', Some('♂')),
+	sym("star", '=', None),
+	sym("ascending_node", '>', Some('☊')),
+	sym("descending_node", '?', Some('☋')),
+	sym("aries", '@', Some('♈')),
+	sym("taurus", '[', Some('♉')),
+	sym("gemini", ']', Some('♊')),
+	sym("cancer", '^', Some('♋')),
+	sym("leo", '_', Some('♌')),
+	sym("virgo", '`', Some('♍')),
+	// there's no libra
+	sym("scorpio", '{', Some('♏')),
+	sym("sagittarius", '|', Some('♐')),
+	sym("capricorn", '}', Some('♑')),
+	sym("aquarius", '~', Some('♒')),
+	sym("pisces", '!', Some('♓')),
+];
+
+/// Just the notation. The letters and digits are plain ASCII.
+pub const MUSIC: &[Symbol] = &[
+	sym("flag_up", '"', None),
+	sym("flag_down", '#', None),
+	sym("half_note_head", '$', Some('\u{1D157}')),
+	sym("quarter_note_head", '&', Some('\u{1D158}')),
+	sym("sharp", '\'', Some('♯')),
+	sym("natural", '(', Some('♮')),
+	sym("flat", ')', Some('♭')),
+	sym("whole_rest", '*', Some('\u{1D13B}')),
+	sym("half_rest", '+', Some('\u{1D13C}')),
+	sym("quarter_rest", '.', Some('\u{1D13D}')),
+	sym("eighth_rest", '-', Some('\u{1D13E}')),
+	sym("old_quarter_rest", ',', None),
+	sym("treble_clef", ';', Some('\u{1D11E}')),
+	sym("bass_clef", '=', Some('\u{1D122}')),
+	sym("alto_clef", '?', Some('\u{1D121}')),
+	sym("ledger_line", '_', None),
+];
+
+/// Weather map symbols. Most of these never got Unicode characters.
+pub const METEOROLOGY: &[Symbol] = &[
+	sym("drizzle", '!', None),
+	sym("snow", '#', None),
+	sym("cold_front", '$', None),
+	sym("warm_front", '&', None),
+	sym("pennant", '\'', None),
+	sym("sandstorm", '^', None),
+	sym("haze", '_', Some('∞')),
+	sym("thunderstorm", '|', Some('☈')),
+	sym("hurricane", '~', None),
+];
+
+/// Plot markers, on A to P. (a to p are the same again.)
+pub const MARKERS: &[Symbol] = &[
+	sym("circle", 'A', Some('○')),
+	sym("square", 'B', Some('□')),
+	sym("triangle", 'C', Some('△')),
+	sym("diamond", 'D', Some('◇')),
+	sym("star", 'E', Some('☆')),
+	sym("hollow_cross", 'F', Some('✙')),
+	sym("plus", 'G', Some('+')),
+	sym("cross", 'H', Some('×')),
+	sym("asterisk", 'I', Some('*')),
+	sym("filled_circle", 'J', Some('●')),
+	sym("filled_square", 'K', Some('■')),
+	sym("filled_triangle", 'L', Some('▲')),
+	sym("filled_triangle_left", 'M', Some('◀')),
+	sym("filled_triangle_down", 'N', Some('▼')),
+	sym("filled_triangle_right", 'O', Some('▶')),
+	sym("triangle_down", 'P', Some('▽')),
+];
+
+/// A grab bag: map symbols, shapes, card suits and so on.
+pub const SYMBOLIC: &[Symbol] = &[
+	sym("bullet", 'A', Some('•')),
+	sym("circle", 'H', Some('○')),
+	sym("square", 'I', Some('□')),
+	sym("triangle", 'J', Some('△')),
+	sym("diamond", 'K', Some('◇')),
+	sym("star", 'L', Some('☆')),
+	sym("plus", 'M', Some('+')),
+	sym("cross", 'N', Some('×')),
+	sym("asterisk", 'O', Some('*')),
+	sym("filled_circle", 'P', Some('●')),
+	sym("filled_square", 'Q', Some('■')),
+	sym("filled_triangle", 'R', Some('▲')),
+	sym("flag", 'V', Some('⚑')),
+	sym("anchor", 'X', Some('⚓')),
+	sym("umbrella", 'Y', Some('☂')),
+	sym("star_of_david", '`', Some('✡')),
+	sym("bell", 'a', None),
+	sym("spade", 'm', Some('♤')),
+	sym("heart", 'n', Some('♡')),
+	sym("diamond_suit", 'o', Some('♢')),
+	sym("club", 'p', Some('♧')),
+];
+
+/// The table for one of the bundled fonts, by file name, with or without
+/// the `.jhf`.
+pub fn table(font: &str) -> Option<&'static [Symbol]> {
+	Some(match font.strip_suffix(".jhf").unwrap_or(font) {
+		"astrology" => ASTROLOGY,
+		"music" => MUSIC,
+		"meteorology" => METEOROLOGY,
+		"markers" => MARKERS,
+		"symbolic" => SYMBOLIC,
+		_ => return None,
+	})
+}
+
+const FONTS: [&str; 5] = ["astrology", "music", "meteorology", "markers", "symbolic"];
+
+fn named(table: &[Symbol], name: &str) -> Option<Symbol> {
+	table.iter().find(|s| s.name == name).copied()
+}
+
+pub fn astrology(name: &str) -> Option<Symbol> {
+	named(ASTROLOGY, name)
+}
+
+pub fn music(name: &str) -> Option<Symbol> {
+	named(MUSIC, name)
+}
+
+pub fn meteorology(name: &str) -> Option<Symbol> {
+	named(METEOROLOGY, name)
+}
+
+pub fn markers(name: &str) -> Option<Symbol> {
+	named(MARKERS, name)
+}
+
+pub fn symbolic(name: &str) -> Option<Symbol> {
+	named(SYMBOLIC, name)
+}
+
+/// The symbol in `table` that's drawn as `c`, like `♭` in [`MUSIC`].
+pub fn by_unicode(table: &[Symbol], c: char) -> Option<Symbol> {
+	table.iter().find(|s| s.unicode == Some(c)).copied()
+}
+
+/// `query` in lowercase, with the words joined by underscores, the way the
+/// names are written.
+fn normalize(query: &str) -> String {
+	query.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.map(str::to_lowercase)
+		.collect::<Vec<_>>()
+		.join("_")
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b: Vec<char> = b.chars().collect();
+	let mut row: Vec<usize> = (0..=b.len()).collect();
+	for (i, ca) in a.chars().enumerate() {
+		let mut diag = row[0];
+		row[0] = i + 1;
+		for (j, &cb) in b.iter().enumerate() {
+			let next = (diag + (ca != cb) as usize).min(row[j] + 1).min(row[j + 1] + 1);
+			diag = row[j + 1];
+			row[j + 1] = next;
+		}
+	}
+	row[b.len()]
+}
+
+/// How far `name` is from `query` (already normalized), lower is closer.
+/// Every word of the query showing up in the name counts, and beats any
+/// number of typos; otherwise a couple of typos are let through.
+fn score(name: &str, query: &str) -> Option<usize> {
+	if name == query {
+		return Some(0);
+	}
+	
+	let words: Vec<_> = query.split('_').collect();
+	if words.iter().all(|word| name.contains(word)) {
+		let matched: usize = words.iter().map(|word| word.len()).sum();
+		return Some(1 + name.len() - matched.min(name.len()));
+	}
+	
+	let distance = edit_distance(name, query);
+	if distance <= (query.len() / 4).max(1) {
+		Some(100 + distance)
+	} else {
+		None
+	}
+}
+
+/// Every symbol in every font whose name looks like `query`, closest
+/// first, with the font it's in. `"clef"` finds all three clefs, and
+/// `"Trebble Clef"` still finds the treble clef.
+pub fn search(query: &str) -> Vec<(&'static str, Symbol)> {
+	let query = normalize(query);
+	if query.is_empty() {
+		return Vec::new();
+	}
+	
+	let mut found: Vec<_> = FONTS.iter()
+		.flat_map(|&font| table(font).unwrap().iter().map(move |&s| (font, s)))
+		.filter_map(|(font, s)| Some((score(s.name, &query)?, font, s)))
+		.collect();
+	found.sort_by_key(|&(score, ..)| score);
+	
+	found.into_iter().map(|(_, font, s)| (font, s)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	
+	#[test]
+	fn names_and_search() {
+		let treble = music("treble_clef").unwrap();
+		assert_eq!(treble.key, ';');
+		assert_eq!(treble.unicode, Some('𝄞'));
+		assert_eq!(astrology("aries").unwrap().unicode, Some('♈'));
+		assert_eq!(by_unicode(ASTROLOGY, '☉'), astrology("sun"));
+		assert_eq!(music("tuba"), None);
+		assert_eq!(table("markers.jhf"), Some(MARKERS));
+		
+		let clefs = search("clef");
+		assert_eq!(clefs.len(), 3);
+		assert!(clefs.iter().all(|&(font, _)| font == "music"));
+		
+		assert_eq!(search("Trebble Clef")[0], ("music", treble));
+		assert_eq!(search("triangle")[0].1.name, "triangle");
+		assert!(search("qwerty").is_empty());
+		assert!(search("").is_empty());
+	}
+	
+	// The names and characters are unique in each table, and every key
+	// has something drawn on it.
+	#[test]
+	fn tables_are_sane() {
+		for font in FONTS {
+			let hershey = test_font(font);
+			let table = table(font).unwrap();
+			
+			for (i, s) in table.iter().enumerate() {
+				let later = &table[i + 1..];
+				assert!(later.iter().all(|t| t.name != s.name && t.key != s.key), "{} {}", font, s.name);
+				assert!(s.unicode.is_none() || later.iter().all(|t| t.unicode != s.unicode), "{} {}", font, s.name);
+				assert!(!s.glyph(&hershey).unwrap().vertex_data.is_empty(), "{} {}", font, s.name);
+			}
+		}
+	}
+}
@@ -0,0 +1,152 @@
+use crate::Point;
+
+/// A 2×3 affine matrix. Points go through it as
+/// `x' = m[0][0] * x + m[0][1] * y + m[0][2]` and
+/// `y' = m[1][0] * x + m[1][1] * y + m[1][2]`.
+///
+/// Hershey coordinates have +y pointing *down*, so a positive
+/// [`rotate`](Transform::rotate) turns things clockwise on screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform(pub [[f64; 3]; 2]);
+
+impl Default for Transform {
+	fn default() -> Self { Self::IDENTITY }
+}
+
+impl Transform {
+	/// Does nothing. Very good at it.
+	pub const IDENTITY: Self = Transform([
+		[1.0, 0.0, 0.0],
+		[0.0, 1.0, 0.0],
+	]);
+	
+	pub const fn translate(x: f64, y: f64) -> Self {
+		Transform([
+			[1.0, 0.0, x],
+			[0.0, 1.0, y],
+		])
+	}
+	
+	/// Non-uniform scale. Use [`uniform_scale`](Self::uniform_scale)
+	/// for the old `s: f64` behaviour.
+	pub const fn scale(x: f64, y: f64) -> Self {
+		Transform([
+			[x, 0.0, 0.0],
+			[0.0, y, 0.0],
+		])
+	}
+	
+	pub const fn uniform_scale(s: f64) -> Self {
+		Self::scale(s, s)
+	}
+	
+	/// Rotate around the origin by `angle` radians.
+	pub fn rotate(angle: f64) -> Self {
+		let (sin, cos) = angle.sin_cos();
+		Transform([
+			[cos, -sin, 0.0],
+			[sin,  cos, 0.0],
+		])
+	}
+	
+	/// Shear by `x` radians along the x axis and `y` radians along the y axis.
+	pub fn skew(x: f64, y: f64) -> Self {
+		Transform([
+			[1.0, x.tan(), 0.0],
+			[y.tan(), 1.0, 0.0],
+		])
+	}
+	
+	/// Slant text to the right by `angle` radians, like an italic.
+	/// (Remember +y is down, so the top of a glyph is at -y.)
+	pub fn oblique(angle: f64) -> Self {
+		Self::skew(-angle, 0.0)
+	}
+	
+	/// Flip left-to-right, for writing on the back of things.
+	pub const fn mirror_x() -> Self {
+		Self::scale(-1.0, 1.0)
+	}
+	
+	/// Flip upside down.
+	pub const fn mirror_y() -> Self {
+		Self::scale(1.0, -1.0)
+	}
+	
+	/// Do `self` first, then `next`.
+	pub fn then(&self, next: &Transform) -> Transform {
+		let [[a, b, c], [d, e, f]] = next.0;
+		let [[g, h, i], [j, k, l]] = self.0;
+		Transform([
+			[a * g + b * j, a * h + b * k, a * i + b * l + c],
+			[d * g + e * j, d * h + e * k, d * i + e * l + f],
+		])
+	}
+	
+	pub fn apply(&self, p: Point) -> Point {
+		let [[a, b, c], [d, e, f]] = self.0;
+		(a * p.0 + b * p.1 + c, d * p.0 + e * p.1 + f)
+	}
+	
+	/// Like [`apply`](Self::apply), but ignores the translation part.
+	/// Good for directions and advances.
+	pub fn apply_vector(&self, v: Point) -> Point {
+		let [[a, b, _], [d, e, _]] = self.0;
+		(a * v.0 + b * v.1, d * v.0 + e * v.1)
+	}
+	
+	/// Negative if this flips things inside out (mirrors).
+	pub fn determinant(&self) -> f64 {
+		let [[a, b, _], [d, e, _]] = self.0;
+		a * e - b * d
+	}
+	
+	pub fn invert(&self) -> Option<Transform> {
+		let det = self.determinant();
+		if det == 0.0 || !det.is_finite() { return None; }
+		
+		let [[a, b, c], [d, e, f]] = self.0;
+		let (ia, ib, id, ie) = (e / det, -b / det, -d / det, a / det);
+		Some(Transform([
+			[ia, ib, -(ia * c + ib * f)],
+			[id, ie, -(id * c + ie * f)],
+		]))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn close(a: Point, b: Point) -> bool {
+		(a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+	}
+	
+	#[test]
+	fn order_of_operations() {
+		let t = Transform::uniform_scale(2.0).then(&Transform::translate(10.0, 5.0));
+		assert!(close(t.apply((1.0, 1.0)), (12.0, 7.0)));
+		
+		let t = Transform::translate(10.0, 5.0).then(&Transform::uniform_scale(2.0));
+		assert!(close(t.apply((1.0, 1.0)), (22.0, 12.0)));
+	}
+	
+	#[test]
+	fn rotate_is_clockwise_on_screen() {
+		let t = Transform::rotate(core::f64::consts::FRAC_PI_2);
+		// right becomes down
+		assert!(close(t.apply((1.0, 0.0)), (0.0, 1.0)));
+	}
+	
+	#[test]
+	fn mirror_and_invert() {
+		let t = Transform::mirror_x().then(&Transform::translate(3.0, 0.0));
+		assert!(t.determinant() < 0.0);
+		assert!(close(t.apply((1.0, 2.0)), (2.0, 2.0)));
+		
+		let inv = t.invert().unwrap();
+		assert!(close(inv.apply(t.apply((4.0, -7.0))), (4.0, -7.0)));
+		assert!(Transform::scale(0.0, 1.0).invert().is_none());
+	}
+}
@@ -0,0 +1,776 @@
+//! Compiling a [`HersheyFont`] into a TrueType font, so it can be used in
+//! apps that have never heard of Hershey.
+//!
+//! Strokes get outlined with [`outline`](crate::outline) and written out as
+//! straight-line `glyf` contours. No curves, no hinting, no kerning.
+
+use crate::{HersheyChar, HersheyError, HersheyFont, Point};
+use crate::layout::LINE_HEIGHT;
+use crate::outline::{self, Pen};
+use crate::transform::Transform;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TtfOptions {
+	/// What to outline the strokes with, in Hershey units.
+	/// Its width picks the weight class too.
+	pub pen: Pen,
+	
+	/// Don't outline anything: every stroke becomes a zero-area contour that
+	/// goes there and back again. Most apps will draw nothing (or a hairline),
+	/// but plotter software that traces outlines gets the original centre lines.
+	pub single_line: bool,
+	
+	/// How many font units one [`LINE_HEIGHT`] becomes. TrueType wants
+	/// 16 to 16384.
+	pub units_per_em: u16,
+	
+	/// Where the baseline is, in Hershey units. It's 9 for the bundled fonts.
+	pub baseline: f64,
+	
+	/// Done to every glyph's strokes first, in Hershey units around its
+	/// origin, before they get outlined (so the pen stays round). Widths
+	/// follow whatever it does to x.
+	pub transform: Transform,
+}
+
+impl Default for TtfOptions {
+	fn default() -> Self {
+		TtfOptions {
+			pen: Pen { width: 2.0, ..Pen::default() },
+			single_line: false,
+			units_per_em: 1024,
+			baseline: 9.0,
+			transform: Transform::IDENTITY,
+		}
+	}
+}
+
+impl TtfOptions {
+	fn scale(&self) -> f64 {
+		self.units_per_em as f64 / LINE_HEIGHT
+	}
+	
+	/// Hershey units (relative to the glyph's left edge) to font units.
+	fn units(&self, p: Point, left_hand: f64) -> Result<(i16, i16), HersheyError> {
+		let scale = self.scale();
+		Ok((
+			round_i16((p.0 - left_hand) * scale)?,
+			round_i16((self.baseline - p.1) * scale)?,
+		))
+	}
+}
+
+/// Round to whole font units, if that fits in an `i16`.
+fn round_i16(v: f64) -> Result<i16, HersheyError> {
+	let v = v.round();
+	if (i16::MIN as f64..=i16::MAX as f64).contains(&v) { Ok(v as i16) } else { Err(HersheyError::OutOfRange) }
+}
+
+/// A box, for characters the font doesn't have.
+const NOTDEF: &str = "    0  6JZMFWFW[M[MF";
+
+const ON_CURVE: u8 = 0x01;
+const X_SHORT: u8 = 0x02;
+const Y_SHORT: u8 = 0x04;
+const X_SAME: u8 = 0x10;
+const Y_SAME: u8 = 0x20;
+
+/// A glyph in font units, +y up.
+struct Glyph {
+	contours: Vec<Vec<(i16, i16)>>,
+	advance: u16,
+}
+
+impl Glyph {
+	fn new(chr: &HersheyChar, options: &TtfOptions) -> Result<Self, HersheyError> {
+		let strokes = chr.transformed(&options.transform);
+		let (left, right) = chr.transformed_edges(&options.transform);
+		
+		let contours = if options.single_line {
+			strokes
+				.into_iter()
+				.map(|mut s| {
+					let back = s.iter().rev().skip(1).take(s.len().saturating_sub(2)).copied().collect::<Vec<_>>();
+					s.extend(back);
+					s
+				})
+				.collect()
+		} else {
+			// outer contours are clockwise on screen, which is what TrueType wants
+			outline::outline_strokes(&strokes, &options.pen)
+		};
+		
+		let mut units = Vec::new();
+		for c in contours {
+			let mut c = c.into_iter().map(|p| options.units(p, left)).collect::<Result<Vec<_>, _>>()?;
+			c.dedup();
+			while c.len() > 1 && c.first() == c.last() { c.pop(); }
+			if c.len() >= if options.single_line { 2 } else { 3 } { units.push(c); }
+		}
+		
+		// hhea works out side bearings from it as an i16
+		let advance = round_i16((right - left).max(0.0) * options.scale())?;
+		Ok(Glyph {
+			contours: units,
+			advance: advance as u16,
+		})
+	}
+	
+	/// `(x_min, y_min, x_max, y_max)`, unless there's nothing there.
+	fn bounds(&self) -> Option<(i16, i16, i16, i16)> {
+		let mut points = self.contours.iter().flatten();
+		let &(x, y) = points.next()?;
+		
+		Some(points.fold((x, y, x, y), |b, &(x, y)| {
+			(b.0.min(x), b.1.min(y), b.2.max(x), b.3.max(y))
+		}))
+	}
+	
+	fn lsb(&self) -> i16 {
+		self.bounds().map_or(0, |b| b.0)
+	}
+	
+	fn point_count(&self) -> usize {
+		self.contours.iter().map(Vec::len).sum()
+	}
+	
+	/// A simple glyph description for the `glyf` table. Empty glyphs are empty.
+	fn encode(&self, out: &mut Vec<u8>) {
+		let (x_min, y_min, x_max, y_max) = match self.bounds() {
+			Some(b) => b,
+			None => return,
+		};
+		
+		put_i16(out, self.contours.len() as i16);
+		for v in [x_min, y_min, x_max, y_max] {
+			put_i16(out, v);
+		}
+		
+		let mut end = 0;
+		for c in &self.contours {
+			end += c.len();
+			put_u16(out, (end - 1) as u16);
+		}
+		put_u16(out, 0); // no instructions
+		
+		let (mut flags, mut xs, mut ys) = (Vec::new(), Vec::new(), Vec::new());
+		let mut prev = (0, 0);
+		for &(x, y) in self.contours.iter().flatten() {
+			let dx = delta(x as i32 - prev.0 as i32, X_SHORT, X_SAME, &mut xs);
+			let dy = delta(y as i32 - prev.1 as i32, Y_SHORT, Y_SAME, &mut ys);
+			flags.push(ON_CURVE | dx | dy);
+			prev = (x, y);
+		}
+		
+		out.extend(flags);
+		out.extend(xs);
+		out.extend(ys);
+	}
+}
+
+/// Write one coordinate delta as small as it'll go, and say how in the flags.
+fn delta(d: i32, short: u8, same: u8, out: &mut Vec<u8>) -> u8 {
+	if d == 0 {
+		same
+	} else if d.abs() < 256 {
+		out.push(d.unsigned_abs() as u8);
+		if d > 0 { short | same } else { short }
+	} else {
+		out.extend_from_slice(&(d as i16).to_be_bytes());
+		0
+	}
+}
+
+fn put_u16(out: &mut Vec<u8>, v: u16) { out.extend_from_slice(&v.to_be_bytes()); }
+fn put_i16(out: &mut Vec<u8>, v: i16) { out.extend_from_slice(&v.to_be_bytes()); }
+fn put_u32(out: &mut Vec<u8>, v: u32) { out.extend_from_slice(&v.to_be_bytes()); }
+
+fn pad(out: &mut Vec<u8>) {
+	while !out.len().is_multiple_of(4) { out.push(0); }
+}
+
+/// Sum of big-endian `u32`s, zero padded at the end.
+fn checksum(data: &[u8]) -> u32 {
+	data.chunks(4).fold(0u32, |sum, chunk| {
+		let mut word = [0; 4];
+		word[..chunk.len()].copy_from_slice(chunk);
+		sum.wrapping_add(u32::from_be_bytes(word))
+	})
+}
+
+/// `searchRange`, `entrySelector` and `rangeShift`, for binary searching
+/// `n` things that are `size` bytes each.
+fn search_params(n: u16, size: u16) -> (u16, u16, u16) {
+	let selector = 15 - n.max(1).leading_zeros() as u16;
+	let range = (1 << selector) * size;
+	(range, selector, n * size - range)
+}
+
+/// Compile `font` into the bytes of a `.ttf` file. Glyph 0 is a box for
+/// missing characters, and glyph `i + 1` is `font.chars[i]`.
+///
+/// Fails with [`HersheyError::OutOfRange`] if `units_per_em` isn't one
+/// TrueType allows, or something comes out too big for its field at that
+/// size.
+pub fn compile(font: &HersheyFont, options: &TtfOptions) -> Result<Vec<u8>, HersheyError> {
+	if !(16..=16384).contains(&options.units_per_em) { return Err(HersheyError::OutOfRange); }
+	
+	let notdef = HersheyChar::new_from_str(NOTDEF).unwrap();
+	let glyphs = std::iter::once(&notdef)
+		.chain(&font.chars)
+		.map(|c| Glyph::new(c, options))
+		.collect::<Result<Vec<_>, _>>()?;
+	
+	let mut char_map = font.char_map()
+		.into_iter()
+		.map(|(c, i)| (c as u32, i as u16 + 1))
+		.collect::<Vec<_>>();
+	char_map.sort_by_key(|m| m.0);
+	char_map.dedup_by_key(|m| m.0);
+	
+	let family = if font.name.is_empty() { "Hershey" } else { &font.name };
+	let metrics = Metrics::new(font, &glyphs, options)?;
+	
+	let (glyf, loca) = glyf_and_loca(&glyphs);
+	Ok(assemble(vec![
+		(*b"OS/2", os2(&glyphs, &char_map, &metrics, options)),
+		(*b"cmap", cmap(&char_map)),
+		(*b"glyf", glyf),
+		(*b"head", head(&metrics, options)),
+		(*b"hhea", hhea(&glyphs, &metrics)),
+		(*b"hmtx", hmtx(&glyphs)),
+		(*b"loca", loca),
+		(*b"maxp", maxp(&glyphs)),
+		(*b"name", name(family)),
+		(*b"post", post(&glyphs, &metrics)),
+	]))
+}
+
+/// Numbers more than one table wants.
+struct Metrics {
+	bounds: (i16, i16, i16, i16),
+	ascender: i16,
+	descender: i16,
+	x_height: i16,
+	cap_height: i16,
+	stroke: i16,
+}
+
+impl Metrics {
+	fn new(font: &HersheyFont, glyphs: &[Glyph], options: &TtfOptions) -> Result<Self, HersheyError> {
+		let bounds = glyphs.iter()
+			.filter_map(Glyph::bounds)
+			.reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+			.unwrap_or((0, 0, 0, 0));
+		
+		// a line's worth, centred on the glyph origins like `layout_str` does
+		let scale = options.scale();
+		let to_units = |y: f64| round_i16((options.baseline - y) * scale);
+		
+		let top_of = |c: char| font.glyph_for(c)
+			.and_then(|chr| chr.transformed(&options.transform).into_iter().flatten().map(|p| p.1).reduce(f64::min))
+			.map_or(Ok(0), to_units);
+		
+		Ok(Metrics {
+			bounds,
+			ascender: to_units(-LINE_HEIGHT / 2.0)?,
+			descender: to_units(LINE_HEIGHT / 2.0)?,
+			x_height: top_of('x')?,
+			cap_height: top_of('H')?,
+			stroke: if options.single_line { 0 } else { round_i16(options.pen.width * scale)? },
+		})
+	}
+}
+
+fn glyf_and_loca(glyphs: &[Glyph]) -> (Vec<u8>, Vec<u8>) {
+	let (mut glyf, mut loca) = (Vec::new(), Vec::new());
+	
+	for g in glyphs {
+		put_u32(&mut loca, glyf.len() as u32);
+		g.encode(&mut glyf);
+		pad(&mut glyf);
+	}
+	put_u32(&mut loca, glyf.len() as u32);
+	
+	(glyf, loca)
+}
+
+fn head(metrics: &Metrics, options: &TtfOptions) -> Vec<u8> {
+	let mut out = Vec::new();
+	put_u16(&mut out, 1); // version 1.0
+	put_u16(&mut out, 0);
+	put_u32(&mut out, 0x0001_0000); // font revision
+	put_u32(&mut out, 0); // checksum adjustment, filled in by `assemble`
+	put_u32(&mut out, 0x5F0F_3CF5);
+	put_u16(&mut out, 0b1011); // baseline at 0, lsb at 0, integer scaling
+	put_u16(&mut out, options.units_per_em);
+	out.extend([0; 16]); // created and modified, in 1904
+	
+	let (x_min, y_min, x_max, y_max) = metrics.bounds;
+	for v in [x_min, y_min, x_max, y_max] {
+		put_i16(&mut out, v);
+	}
+	
+	put_u16(&mut out, 0); // mac style
+	put_u16(&mut out, 8); // smallest readable size
+	put_i16(&mut out, 2); // font direction hint
+	put_i16(&mut out, 1); // long loca offsets
+	put_i16(&mut out, 0); // glyph data format
+	out
+}
+
+fn hhea(glyphs: &[Glyph], metrics: &Metrics) -> Vec<u8> {
+	let inked = glyphs.iter().filter_map(|g| Some((g, g.bounds()?)));
+	let min_lsb = inked.clone().map(|(_, b)| b.0).min().unwrap_or(0);
+	let min_rsb = inked.clone().map(|(g, b)| g.advance as i16 - b.2).min().unwrap_or(0);
+	let max_extent = inked.map(|(_, b)| b.2).max().unwrap_or(0);
+	
+	let mut out = Vec::new();
+	put_u32(&mut out, 0x0001_0000);
+	put_i16(&mut out, metrics.ascender);
+	put_i16(&mut out, metrics.descender);
+	put_i16(&mut out, 0); // line gap
+	put_u16(&mut out, glyphs.iter().map(|g| g.advance).max().unwrap_or(0));
+	put_i16(&mut out, min_lsb);
+	put_i16(&mut out, min_rsb);
+	put_i16(&mut out, max_extent);
+	put_i16(&mut out, 1); // caret slope rise
+	put_i16(&mut out, 0); // caret slope run
+	put_i16(&mut out, 0); // caret offset
+	out.extend([0; 8]); // reserved
+	put_i16(&mut out, 0); // metric data format
+	put_u16(&mut out, glyphs.len() as u16);
+	out
+}
+
+fn hmtx(glyphs: &[Glyph]) -> Vec<u8> {
+	let mut out = Vec::new();
+	for g in glyphs {
+		put_u16(&mut out, g.advance);
+		put_i16(&mut out, g.lsb());
+	}
+	out
+}
+
+fn maxp(glyphs: &[Glyph]) -> Vec<u8> {
+	let mut out = Vec::new();
+	put_u32(&mut out, 0x0001_0000);
+	put_u16(&mut out, glyphs.len() as u16);
+	put_u16(&mut out, glyphs.iter().map(Glyph::point_count).max().unwrap_or(0) as u16);
+	put_u16(&mut out, glyphs.iter().map(|g| g.contours.len()).max().unwrap_or(0) as u16);
+	put_u16(&mut out, 0); // composite points
+	put_u16(&mut out, 0); // composite contours
+	put_u16(&mut out, 2); // zones
+	out.extend([0; 16]); // no hinting, no composites, so nothing else is used
+	out
+}
+
+/// Which `ulUnicodeRange` bits go with which ranges. Just the ones that
+/// Hershey fonts could plausibly have.
+const UNICODE_RANGES: &[(u32, u32, u32)] = &[
+	(0, 0x0000, 0x007F), // Basic Latin
+	(1, 0x0080, 0x00FF), // Latin-1 Supplement
+	(2, 0x0100, 0x017F), // Latin Extended-A
+	(7, 0x0370, 0x03FF), // Greek and Coptic
+	(9, 0x0400, 0x04FF), // Cyrillic
+	(31, 0x2000, 0x206F), // General Punctuation
+	(38, 0x2200, 0x22FF), // Mathematical Operators
+	(45, 0x2500, 0x257F), // Box Drawing
+	(46, 0x25A0, 0x25FF), // Geometric Shapes
+	(47, 0x2600, 0x26FF), // Miscellaneous Symbols
+	(48, 0x3000, 0x303F), // CJK Symbols and Punctuation
+	(49, 0x3040, 0x309F), // Hiragana
+	(50, 0x30A0, 0x30FF), // Katakana
+	(57, 0x10000, 0x10FFFF), // anything outside the BMP
+	(59, 0x4E00, 0x9FFF), // CJK Unified Ideographs
+];
+
+fn os2(glyphs: &[Glyph], char_map: &[(u32, u16)], metrics: &Metrics, options: &TtfOptions) -> Vec<u8> {
+	let upm = options.units_per_em as f64;
+	let em = |f: f64| (upm * f).round() as i16;
+	
+	let advances = glyphs.iter().map(|g| g.advance as u32).filter(|&a| a > 0).collect::<Vec<_>>();
+	let average = advances.iter().sum::<u32>() / (advances.len() as u32).max(1);
+	
+	let weight = if options.single_line {
+		100
+	} else {
+		((100.0 + options.pen.width * 150.0) / 100.0).round().clamp(1.0, 9.0) as u16 * 100
+	};
+	
+	let mut ranges = [0u32; 4];
+	for &(bit, from, to) in UNICODE_RANGES {
+		if char_map.iter().any(|&(c, _)| (from..=to).contains(&c)) {
+			ranges[bit as usize / 32] |= 1 << (bit % 32);
+		}
+	}
+	
+	let first = char_map.first().map_or(0, |m| m.0.min(0xFFFF) as u16);
+	let last = char_map.last().map_or(0, |m| m.0.min(0xFFFF) as u16);
+	let win_ascent = metrics.ascender.max(metrics.bounds.3);
+	let win_descent = metrics.descender.min(metrics.bounds.1);
+	
+	let mut out = Vec::new();
+	put_u16(&mut out, 4); // version
+	put_i16(&mut out, average as i16);
+	put_u16(&mut out, weight);
+	put_u16(&mut out, 5); // medium width
+	put_u16(&mut out, 0); // installable embedding
+	for v in [em(0.65), em(0.6), 0, em(0.075), em(0.65), em(0.6), 0, em(0.35)] {
+		put_i16(&mut out, v); // subscript and superscript size and offset
+	}
+	put_i16(&mut out, metrics.stroke.max(1));
+	put_i16(&mut out, if metrics.x_height > 0 { metrics.x_height / 2 } else { em(0.25) });
+	put_i16(&mut out, 0); // family class
+	out.extend([0; 10]); // panose
+	for r in ranges {
+		put_u32(&mut out, r);
+	}
+	out.extend(b"NONE");
+	put_u16(&mut out, 0x00C0); // regular, use typo metrics
+	put_u16(&mut out, first);
+	put_u16(&mut out, last);
+	put_i16(&mut out, metrics.ascender);
+	put_i16(&mut out, metrics.descender);
+	put_i16(&mut out, 0); // typo line gap
+	put_u16(&mut out, win_ascent.max(0) as u16);
+	put_u16(&mut out, win_descent.min(0).unsigned_abs());
+	put_u32(&mut out, if ranges[0] & 1 != 0 { 1 } else { 0 }); // Latin 1 code page
+	put_u32(&mut out, 0);
+	put_i16(&mut out, metrics.x_height);
+	put_i16(&mut out, metrics.cap_height);
+	put_u16(&mut out, 0); // default char is .notdef
+	put_u16(&mut out, b' ' as u16); // break char
+	put_u16(&mut out, 1); // max context
+	out
+}
+
+/// Runs of characters that go to consecutive glyphs, as `(first char, last char, first glyph)`.
+fn runs(char_map: &[(u32, u16)]) -> Vec<(u32, u32, u16)> {
+	let mut runs: Vec<(u32, u32, u16)> = Vec::new();
+	
+	for &(c, g) in char_map {
+		match runs.last_mut() {
+			Some(r) if r.1 + 1 == c && r.2 as u32 + (c - r.0) == g as u32 => r.1 = c,
+			_ => runs.push((c, c, g)),
+		}
+	}
+	
+	runs
+}
+
+fn cmap(char_map: &[(u32, u16)]) -> Vec<u8> {
+	let bmp = char_map.iter().copied().filter(|m| m.0 < 0xFFFF).collect::<Vec<_>>();
+	
+	// format 4, for the basic multilingual plane
+	let mut segments = runs(&bmp);
+	segments.push((0xFFFF, 0xFFFF, 0)); // required end marker
+	let seg_count = segments.len() as u16;
+	let (range, selector, shift) = search_params(seg_count, 2);
+	
+	let mut format_4 = Vec::new();
+	put_u16(&mut format_4, 4);
+	put_u16(&mut format_4, 16 + 8 * seg_count);
+	put_u16(&mut format_4, 0); // language
+	put_u16(&mut format_4, seg_count * 2);
+	put_u16(&mut format_4, range);
+	put_u16(&mut format_4, selector);
+	put_u16(&mut format_4, shift);
+	for s in &segments { put_u16(&mut format_4, s.1 as u16); }
+	put_u16(&mut format_4, 0); // reserved
+	for s in &segments { put_u16(&mut format_4, s.0 as u16); }
+	for s in &segments { put_u16(&mut format_4, s.2.wrapping_sub(s.0 as u16)); }
+	for _ in &segments { put_u16(&mut format_4, 0); }
+	
+	let mut subtables = vec![(1, format_4)];
+	
+	// format 12, only if something doesn't fit in format 4
+	if bmp.len() < char_map.len() {
+		let groups = runs(char_map);
+		
+		let mut format_12 = Vec::new();
+		put_u16(&mut format_12, 12);
+		put_u16(&mut format_12, 0);
+		put_u32(&mut format_12, 16 + 12 * groups.len() as u32);
+		put_u32(&mut format_12, 0); // language
+		put_u32(&mut format_12, groups.len() as u32);
+		for (from, to, glyph) in groups {
+			put_u32(&mut format_12, from);
+			put_u32(&mut format_12, to);
+			put_u32(&mut format_12, glyph as u32);
+		}
+		
+		subtables.push((10, format_12));
+	}
+	
+	let mut out = Vec::new();
+	put_u16(&mut out, 0); // version
+	put_u16(&mut out, subtables.len() as u16);
+	
+	let mut offset = 4 + 8 * subtables.len();
+	for (encoding, data) in &subtables {
+		put_u16(&mut out, 3); // Windows
+		put_u16(&mut out, *encoding);
+		put_u32(&mut out, offset as u32);
+		offset += data.len();
+	}
+	for (_, data) in subtables {
+		out.extend(data);
+	}
+	out
+}
+
+fn name(family: &str) -> Vec<u8> {
+	let mut postscript = family.chars()
+		.filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+		.take(63)
+		.collect::<String>();
+	if postscript.is_empty() {
+		postscript = "Hershey".to_string();
+	}
+	let unique = format!("{};Regular", postscript);
+	
+	let strings = [
+		(1, family),
+		(2, "Regular"),
+		(3, &unique),
+		(4, family),
+		(5, "Version 1.000"),
+		(6, &postscript),
+	];
+	
+	let mut out = Vec::new();
+	put_u16(&mut out, 0); // format
+	put_u16(&mut out, strings.len() as u16);
+	put_u16(&mut out, 6 + 12 * strings.len() as u16);
+	
+	let mut storage = Vec::new();
+	for (id, s) in strings {
+		let start = storage.len();
+		for unit in s.encode_utf16() {
+			put_u16(&mut storage, unit);
+		}
+		
+		put_u16(&mut out, 3); // Windows
+		put_u16(&mut out, 1); // Unicode BMP
+		put_u16(&mut out, 0x0409); // English (US)
+		put_u16(&mut out, id);
+		put_u16(&mut out, (storage.len() - start) as u16);
+		put_u16(&mut out, start as u16);
+	}
+	
+	out.extend(storage);
+	out
+}
+
+fn post(glyphs: &[Glyph], metrics: &Metrics) -> Vec<u8> {
+	let advances = glyphs.iter().skip(1).map(|g| g.advance);
+	let fixed_pitch = advances.clone().min() == advances.max();
+	
+	let mut out = Vec::new();
+	put_u32(&mut out, 0x0003_0000); // no glyph names
+	put_u32(&mut out, 0); // italic angle
+	put_i16(&mut out, metrics.descender / 2);
+	put_i16(&mut out, metrics.stroke.max(1));
+	put_u32(&mut out, fixed_pitch as u32);
+	out.extend([0; 16]); // memory usage, unknown
+	out
+}
+
+/// Lay the tables out after a table directory, and fix up `head`'s checksum.
+fn assemble(mut tables: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+	tables.sort_by_key(|t| t.0);
+	let count = tables.len() as u16;
+	let (range, selector, shift) = search_params(count, 16);
+	
+	let mut out = Vec::new();
+	put_u32(&mut out, 0x0001_0000); // TrueType outlines
+	put_u16(&mut out, count);
+	put_u16(&mut out, range);
+	put_u16(&mut out, selector);
+	put_u16(&mut out, shift);
+	
+	let mut offset = 12 + 16 * tables.len();
+	let mut head_at = None;
+	for (tag, data) in &tables {
+		if tag == b"head" { head_at = Some(offset); }
+		
+		out.extend(tag);
+		put_u32(&mut out, checksum(data));
+		put_u32(&mut out, offset as u32);
+		put_u32(&mut out, data.len() as u32);
+		offset += (data.len() + 3) & !3;
+	}
+	
+	for (_, data) in tables {
+		out.extend(data);
+		pad(&mut out);
+	}
+	
+	if let Some(at) = head_at {
+		let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&out));
+		out[at + 8..at + 12].copy_from_slice(&adjustment.to_be_bytes());
+	}
+	
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	use ttf_parser::{Face, GlyphId, OutlineBuilder};
+	
+	fn futura_light() -> HersheyFont {
+		HersheyFont { name: "Futura Light".into(), ..test_font("futural") }
+	}
+	
+	fn u32_at(data: &[u8], at: usize) -> u32 {
+		u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+	}
+	
+	/// Adds up signed areas, +y up, so clockwise is negative.
+	#[derive(Default)]
+	struct Area {
+		start: (f32, f32),
+		last: (f32, f32),
+		contours: Vec<f32>,
+	}
+	
+	impl OutlineBuilder for Area {
+		fn move_to(&mut self, x: f32, y: f32) {
+			self.start = (x, y);
+			self.last = (x, y);
+			self.contours.push(0.0);
+		}
+		fn line_to(&mut self, x: f32, y: f32) {
+			*self.contours.last_mut().unwrap() += (self.last.0 * y - self.last.1 * x) / 2.0;
+			self.last = (x, y);
+		}
+		fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) { unreachable!() }
+		fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) { unreachable!() }
+		fn close(&mut self) {
+			let (x, y) = self.start;
+			self.line_to(x, y);
+		}
+	}
+	
+	#[test]
+	fn checksums_add_up() {
+		let data = compile(&futura_light(), &TtfOptions::default()).unwrap();
+		
+		assert_eq!(checksum(&data), 0xB1B0_AFBA);
+		assert_eq!(data.len() % 4, 0);
+		
+		let count = u16::from_be_bytes([data[4], data[5]]) as usize;
+		assert_eq!(count, 10);
+		for i in 0..count {
+			let record = 12 + 16 * i;
+			let (sum, offset, len) = (u32_at(&data, record + 4), u32_at(&data, record + 8) as usize, u32_at(&data, record + 12) as usize);
+			
+			let mut table = data[offset..offset + len].to_vec();
+			if &data[record..record + 4] == b"head" {
+				table[8..12].fill(0);
+			}
+			assert_eq!(checksum(&table), sum);
+		}
+	}
+	
+	#[test]
+	fn reads_back() {
+		let font = futura_light();
+		let data = compile(&font, &TtfOptions::default()).unwrap();
+		let face = Face::parse(&data, 0).unwrap();
+		
+		assert_eq!(face.units_per_em(), 1024);
+		assert_eq!(face.number_of_glyphs() as usize, font.chars.len() + 1);
+		assert_eq!(face.weight().to_number(), 400);
+		assert!(!face.is_monospaced());
+		
+		let family = face.names().into_iter().find(|n| n.name_id == ttf_parser::name_id::FAMILY);
+		assert_eq!(family.and_then(|n| n.to_string()).as_deref(), Some("Futura Light"));
+		
+		let a = face.glyph_index('A').unwrap();
+		assert_eq!(a, GlyphId(34));
+		assert_eq!(face.glyph_hor_advance(a), Some(font.chars[33].advance() as u16 * 32));
+		assert_eq!(face.glyph_index('é'), None);
+		
+		// 'A' sits on the baseline and is 21 units tall, plus the pen
+		let mut area = Area::default();
+		let bbox = face.outline_glyph(a, &mut area).unwrap();
+		assert_eq!((bbox.y_min, bbox.y_max), (-32, 21 * 32 + 32));
+		assert_eq!(face.glyph_hor_side_bearing(a), Some(bbox.x_min));
+		
+		// outer contours clockwise, the hole counter-clockwise
+		assert_eq!(area.contours.len(), 2);
+		area.contours.sort_by(|a, b| b.abs().total_cmp(&a.abs()));
+		assert!(area.contours[0] < 0.0);
+		assert!(area.contours[1] > 0.0);
+		
+		let space = face.glyph_index(' ').unwrap();
+		assert!(face.outline_glyph(space, &mut Area::default()).is_none());
+	}
+	
+	#[test]
+	fn mirrored() {
+		let font = futura_light();
+		let options = TtfOptions { transform: Transform::mirror_x(), ..TtfOptions::default() };
+		let (plain, mirrored) = (compile(&font, &TtfOptions::default()).unwrap(), compile(&font, &options).unwrap());
+		let (plain, mirrored) = (Face::parse(&plain, 0).unwrap(), Face::parse(&mirrored, 0).unwrap());
+		
+		let f = plain.glyph_index('F').unwrap();
+		let advance = plain.glyph_hor_advance(f).unwrap() as i16;
+		assert_eq!(mirrored.glyph_hor_advance(f), Some(advance as u16));
+		
+		// the upright ends up on the right, and the outline still goes clockwise
+		let before = plain.outline_glyph(f, &mut Area::default()).unwrap();
+		let mut area = Area::default();
+		let after = mirrored.outline_glyph(f, &mut area).unwrap();
+		assert_eq!((after.x_min, after.x_max), (advance - before.x_max, advance - before.x_min));
+		assert!(area.contours.iter().all(|&a| a < 0.0));
+	}
+	
+	#[test]
+	fn single_line_hairlines() {
+		let font = futura_light();
+		let options = TtfOptions { single_line: true, ..TtfOptions::default() };
+		let data = compile(&font, &options).unwrap();
+		let face = Face::parse(&data, 0).unwrap();
+		
+		let mut area = Area::default();
+		face.outline_glyph(face.glyph_index('A').unwrap(), &mut area).unwrap();
+		
+		assert_eq!(area.contours.len(), font.chars[33].strokes().len());
+		assert!(area.contours.iter().all(|&a| a == 0.0));
+		assert_eq!(face.weight().to_number(), 100);
+	}
+	
+	#[test]
+	fn too_big() {
+		let wide = HersheyFont::new("wide", vec![HersheyChar::new_from_str("12345  2!Z~R").unwrap()]);
+		assert!(compile(&wide, &TtfOptions::default()).is_ok());
+		
+		// 93 units from the left edge is more than an i16 at 512 units each
+		let big = TtfOptions { units_per_em: 16384, ..TtfOptions::default() };
+		assert_eq!(compile(&wide, &big), Err(HersheyError::OutOfRange));
+		
+		let huge = TtfOptions { units_per_em: 20000, ..TtfOptions::default() };
+		assert_eq!(compile(&futura_light(), &huge), Err(HersheyError::OutOfRange));
+	}
+	
+	#[test]
+	fn every_font_compiles() {
+		for entry in std::fs::read_dir("../fonts/").unwrap() {
+			let path = entry.unwrap().path();
+			if path.extension().and_then(|e| e.to_str()) != Some("jhf") { continue; }
+			
+			let font = HersheyFont::new_from_str("", &std::fs::read_to_string(&path).unwrap()).unwrap();
+			let data = compile(&font, &TtfOptions::default()).unwrap();
+			let face = Face::parse(&data, 0).unwrap();
+			
+			for i in 0..face.number_of_glyphs() {
+				face.outline_glyph(GlyphId(i), &mut Area::default());
+			}
+		}
+	}
+}
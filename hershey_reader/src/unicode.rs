@@ -0,0 +1,182 @@
+//! Which character each glyph really is, for the bundled fonts that are
+//! laid out in ASCII order but hold something else. Typing `a` in
+//! greek.jhf gets you an α, but only because that's where it was put.
+//! (japanese.jhf isn't in ASCII order at all. It just goes on past `~`.)
+
+use crate::HersheyFont;
+
+/// Stands in for a glyph that isn't any character in particular.
+const NONE: char = '\0';
+
+// Each run starts at its ASCII character and replaces one slot per
+// character. Anything not in a run stays ASCII.
+
+/// Sort of like the Symbol font: C is Χ, F is Φ, Q is Θ...
+/// J and V are filler.
+const GREEK: &[(char, &str)] = &[
+	('A', "ΑΒΧΔΕΦΓΗΙ\0ΚΛΜΝΟΠΘΡΣΤΥ\0ΩΞΨΖ"),
+	('a', "αβχδεφγηι\0κλμνοπθρστυ\0ωξψζ"),
+];
+
+/// Straight down the alphabet from A to X, with Y and Z left blank.
+const GREEK_IN_ORDER: &[(char, &str)] = &[
+	('A', "ΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟΠΡΣΤΥΦΧΨΩ\0\0"),
+	('a', "αβγδεζηθικλμνξοπρστυφχψω\0\0"),
+];
+
+/// Mostly by sound, with the leftovers wherever they'd fit. E was meant
+/// to be Й, but it's missing its breve, so it's just another И.
+const CYRILLIC: &[(char, &str)] = &[
+	('$', "Ыцы"),
+	('A', "АБЭД\0ФГЖИЧКЛМНОПШРСТЮВЩХУЗЕ\\ЪЯЬЦ"),
+	('a', "абэдйфгжичклмнопшрстювщхузеъяь"),
+];
+
+/// The first 26 letters of the alphabet, А to Щ, in order.
+const CYRILLIC_PART_1: &[(char, &str)] = &[
+	('A', "АБВГДЕЖЗИЙКЛМНОПРСТУФХЦЧШЩ"),
+	('a', "абвгдежзийклмнопрстуфхцчшщ"),
+];
+
+/// Lowercase Latin where the capitals would be. The gaps in the lowercase
+/// are the big radical, integral and brackets, which are only pieces of
+/// bigger things.
+const MATH_LOWER: &[(char, &str)] = &[
+	('!', "±∓×⋅"),
+	('&', "≤≥"),
+	(':', "∏∑"),
+	('?', "≠≡"),
+	('A', "abcdefghijklmnopqrstuvwxyz"),
+	('^', "∝∞°"),
+	('a', "§√\0⊂∪⊃∩∈→↑←↓∂∇\0∫\0\0\0\0\0∃ℵ÷‖⊥"),
+	('|', "∠"),
+	('~', "∴"),
+];
+
+/// Same as [`MATH_LOWER`], but with the capitals left alone, and the
+/// braces moved around.
+const MATH_UPPER: &[(char, &str)] = &[
+	('!', "±∓×⋅"),
+	('&', "≤≥"),
+	(':', "∏∑"),
+	('?', "≠≡"),
+	('^', "∝∞°"),
+	('a', "∗√\0⊂∪⊃∩∈→↑←↓∂∇\0∫\0\0\0\0\0∃ℵ÷‖⊥"),
+	('{', "\0∠{∴"),
+];
+
+/// A few kanji, then the kana in table order, plain, then voiced. The table
+/// has holes (yi, ye, wu) that are filled with repeats of い, え and う, and
+/// each set is padded out with repeats of the full stop. Those all get
+/// `None`, so each character only has one glyph.
+const JAPANESE: &str = concat!(
+	"。、ー日月年大国人東中本京出時上名十一二三四五六七八九\0\0\0\0\0\0",
+	"あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめも",
+	"や\0ゆ\0よらりるれろわゐ\0ゑをん\0\0\0\0",
+	"がぎぐげござじずぜぞだぢづでどばびぶべぼぱぴぷぺぽ",
+	"アイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモ",
+	"ヤ\0ユ\0ヨラリルレロワヰ\0ヱヲン\0\0\0\0",
+	"ガギグゲゴザジズゼゾダヂヅデドバビブベボパピプペポ",
+);
+
+fn slot(c: char) -> Option<char> {
+	if c == NONE { None } else { Some(c) }
+}
+
+/// ASCII order from the space to the `~`, with `runs` swapped in.
+fn remap(runs: &[(char, &str)]) -> Vec<Option<char>> {
+	let mut map: Vec<_> = (' '..='~').map(Some).collect();
+	
+	for &(start, run) in runs {
+		let start = start as usize - ' ' as usize;
+		for (dst, c) in map[start..].iter_mut().zip(run.chars()) {
+			*dst = slot(c);
+		}
+	}
+	
+	map
+}
+
+/// The map for one of the bundled fonts, to go in [`HersheyFont::unicode`].
+/// `name` is the file name, with or without the `.jhf`. Fonts that really
+/// are in ASCII order (and ones this doesn't know) get `None`.
+pub fn builtin(name: &str) -> Option<Vec<Option<char>>> {
+	let runs = match name.strip_suffix(".jhf").unwrap_or(name) {
+		"greek" => GREEK,
+		"greekc" | "greeks" => GREEK_IN_ORDER,
+		"cyrillic" => CYRILLIC,
+		"cyrilc_1" => CYRILLIC_PART_1,
+		"mathlow" => MATH_LOWER,
+		"mathupp" => MATH_UPPER,
+		"japanese" => return Some(JAPANESE.chars().map(slot).collect()),
+		_ => return None,
+	};
+	
+	Some(remap(runs))
+}
+
+impl HersheyFont {
+	/// Fill in [`unicode`](Self::unicode) from [`builtin`], going by
+	/// [`name`](Self::name). Leaves the font alone if there isn't a map for it.
+	pub fn with_builtin_unicode(mut self) -> Self {
+		if let Some(map) = builtin(&self.name) {
+			self.unicode = Some(map);
+		}
+		self
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_font;
+	
+	#[test]
+	fn finds_greek_and_cyrillic() {
+		let greek = test_font("greek").with_builtin_unicode();
+		assert_eq!(greek.glyph_for('α'), Some(&greek.chars[(b'a' - b' ') as usize]));
+		assert_eq!(greek.glyph_for('Θ'), Some(&greek.chars[(b'Q' - b' ') as usize]));
+		assert_eq!(greek.glyph_for('!'), Some(&greek.chars[1]));
+		assert_eq!(greek.glyph_for('a'), None);
+		
+		let greeks = test_font("greeks").with_builtin_unicode();
+		assert_eq!(greeks.glyph_for('Θ'), Some(&greeks.chars[(b'H' - b' ') as usize]));
+		assert_eq!(builtin("greeks.jhf"), greeks.unicode);
+		
+		let cyrillic = test_font("cyrillic").with_builtin_unicode();
+		assert_eq!(cyrillic.glyph_for('Ж'), Some(&cyrillic.chars[(b'H' - b' ') as usize]));
+		assert_eq!(cyrillic.glyph_for('я'), Some(&cyrillic.chars[(b'}' - b' ') as usize]));
+		assert_eq!(cyrillic.glyph_for('Й'), None);
+		
+		let mathlow = test_font("mathlow").with_builtin_unicode();
+		assert_eq!(mathlow.glyph_for('x'), Some(&mathlow.chars[(b'X' - b' ') as usize]));
+		assert_eq!(mathlow.glyph_for('∞'), Some(&mathlow.chars[(b'_' - b' ') as usize]));
+		
+		let japanese = test_font("japanese").with_builtin_unicode();
+		assert_eq!(japanese.glyph_for('日'), Some(&japanese.chars[3]));
+		assert_eq!(japanese.glyph_for('あ'), Some(&japanese.chars[33]));
+		assert_eq!(japanese.glyph_for('ゆ'), Some(&japanese.chars[70]));
+		assert_eq!(japanese.glyph_for('ポ'), japanese.chars.last());
+		
+		let futural = test_font("futural").with_builtin_unicode();
+		assert!(futural.unicode.is_none());
+	}
+	
+	// Every map is the right length, and doesn't claim a character twice,
+	// or `char_map` would send exporters two glyphs for it.
+	#[test]
+	fn maps_are_sane() {
+		for name in ["greek", "greekc", "greeks", "cyrillic", "cyrilc_1", "mathlow", "mathupp", "japanese"] {
+			let map = builtin(name).unwrap();
+			// one per glyph, except the DEL at the end of the ASCII ones
+			let glyphs = test_font(name).with_builtin_unicode().chars.len();
+			assert_eq!(map.len(), if name == "japanese" { glyphs } else { glyphs - 1 }, "{}", name);
+			
+			let mut seen: Vec<_> = map.iter().flatten().collect();
+			seen.sort();
+			let count = seen.len();
+			seen.dedup();
+			assert_eq!(seen.len(), count, "{}", name);
+		}
+	}
+}
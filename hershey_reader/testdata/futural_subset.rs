@@ -0,0 +1,48 @@
+//! `futural`, generated by hershey_reader. Don't edit this by hand.
+//!
+//! Points are `(x, y)` in Hershey units, with +y down and the origin in
+//! the middle of the glyph. 4 glyphs, 92 bytes of tables.
+
+/// Every character there's a glyph for, sorted. Glyphs are in this order too.
+pub const CHARS: [char; 4] = [
+	' ', '!', 'H', 'i',
+];
+
+/// Where each glyph's left edge is, from its origin.
+pub const LEFT_HAND: [i8; 4] = [
+	-8, -5, -11, -4,
+];
+
+/// How far along the next glyph's left edge is from this one's.
+pub const ADVANCES: [i16; 4] = [
+	16, 10, 22, 8,
+];
+
+/// Glyph `g`'s strokes are `STROKES[GLYPHS[g]..GLYPHS[g + 1]]`.
+pub const GLYPHS: [u16; 5] = [
+	0, 0, 2, 5, 7,
+];
+
+/// Where each stroke ends in [`POINTS`]. Each one starts where the one before it ended.
+pub const STROKES: [u16; 7] = [
+	2, 7, 9, 11, 13, 18, 20,
+];
+
+pub const POINTS: [(i8, i8); 20] = [
+	(0, -12), (0, 2), (0, 7), (-1, 8), (0, 9), (1, 8), (0, 7), (-7, -12),
+	(-7, 9), (7, -12), (7, 9), (-7, -2), (7, -2), (-1, -12), (0, -11), (1, -12),
+	(0, -13), (-1, -12), (0, -5), (0, 9),
+];
+
+/// Which glyph `c` is, if there is one.
+pub fn glyph(c: char) -> Option<usize> {
+	CHARS.binary_search(&c).ok()
+}
+
+/// Each of a glyph's strokes, as a run of connected points.
+pub fn strokes(glyph: usize) -> impl Iterator<Item = &'static [(i8, i8)]> {
+	(GLYPHS[glyph] as usize..GLYPHS[glyph + 1] as usize).map(|s| {
+		let start = if s == 0 { 0 } else { STROKES[s - 1] as usize };
+		&POINTS[start..STROKES[s] as usize]
+	})
+}
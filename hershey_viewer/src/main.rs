@@ -1,5 +1,6 @@
-use std::fs::{read_dir, read_to_string};
+use std::fs::{read, read_dir, read_to_string};
 use std::ffi::OsStr;
+use std::path::Path;
 use std::rc::Rc;
 use std::num::NonZeroU32;
 
@@ -12,6 +13,10 @@ use winit::window::WindowBuilder;
 // use nanorand::WyRand;
 
 use hershey_reader::*;
+use hershey_reader::transform::Transform;
+use hershey_reader::layout::{layout_str, Glyphs};
+use hershey_reader::rich::{layout_rich, RichText, SpanStyle};
+use hershey_reader::pack::Pack;
 
 mod bresenham;
 use bresenham::*;
@@ -32,18 +37,29 @@ fn main() -> std::io::Result<()> {
 			println!("Falling back to default font directory (the one inside this repository)");
 			"fonts/".into()
 		});
-	let mut fonts: Vec<(String, Vec<HersheyChar>)> = Vec::new();
+	let mut fonts: Vec<HersheyFont> = Vec::new();
 	
-	let dir = read_dir(look_in)
-		.map_err(|dir| {
+	// a font pack loads a lot faster than a directory full of .jhf files
+	let is_pack = Path::new(look_in).extension().and_then(OsStr::to_str) == Some("hpk");
+	if is_pack {
+		let bytes = read(look_in)?;
+		let pack = Pack::new(&bytes)
+			.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{:?}", e)))?;
+		
+		fonts.extend(pack.fonts().map(|font| font.to_font()));
+		println!("Loaded {} fonts from `{}`.", fonts.len(), Path::new(look_in).display());
+	}
+	
+	let dir = (!is_pack).then(|| read_dir(look_in))
+		.transpose()
+		.inspect_err(|_| {
 			println!(r"i need some fonts to view.
-			supply a directory of .jhf fonts as my first arg?
+			supply a directory of .jhf fonts (or a .hpk font pack) as my first arg?
 			
 			anyway here's the ugly error:");
-			dir
 		})?;
 	
-	for entry in dir {
+	for entry in dir.into_iter().flatten() {
 		let entry = entry?;
 		let path = entry.path();
 		let mut success = false;
@@ -65,7 +81,7 @@ fn main() -> std::io::Result<()> {
 					let filename = path.file_name()
 						.map(|x| x.to_string_lossy().into_owned())
 						.unwrap_or_else(||"oops".to_string());
-					fonts.push((filename, font));
+					fonts.push(HersheyFont::new(filename, font));
 					success = true;
 				}
 			}
@@ -135,7 +151,7 @@ fn main() -> std::io::Result<()> {
 	
 	let ui_font = fonts.iter()
 		.enumerate()
-		.find(|(_, (name, _))| name == "futural.jhf")
+		.find(|(_, font)| font.name == "futural.jhf")
 		.map(|it| it.0)
 		.unwrap_or(0);
 	
@@ -190,17 +206,14 @@ OVER THE LAZY DOG
 			
 			Event::WindowEvent {
 				event: WindowEvent::MouseWheel {
-					delta, ..
+					delta: MouseScrollDelta::LineDelta(x, y), ..
 				}, ..
-			} => { match delta {
-				MouseScrollDelta::LineDelta(x, y) => {
-					let sensitivity = 1.5;
-					let x = (x * sensitivity).floor() as i32;
-					let y = (y * sensitivity).floor() as i32;
-					mouse_scroll = (x, y);
-				},
-				_ => {}
-			}; },
+			} => {
+				let sensitivity = 1.5;
+				let x = (x * sensitivity).floor() as i32;
+				let y = (y * sensitivity).floor() as i32;
+				mouse_scroll = (x, y);
+			},
 			
 			Event::WindowEvent {
 				event: WindowEvent::MouseInput {
@@ -306,14 +319,14 @@ OVER THE LAZY DOG
 							_ => {}
 						}
 						
-						let chrmap_camera_max = 0.max((font.1.len() as Coord - 1) / GRID_CELLS.0 + 1 - GRID_CELLS.1);
+						let chrmap_camera_max = 0.max((font.chars.len() as Coord - 1) / GRID_CELLS.0 + 1 - GRID_CELLS.1);
 						
 						if vec2_within_bounds(mouse, CHRMAP_TL, CHRMAP_BR) {
 							if mouse_click {
 								let mouse = invlerp_vec(CHRMAP_TL_F, CHRMAP_BR_F, (mouse.0 as f64, mouse.1 as f64));
 								let mouse = ((mouse.0 * GRID_CELLS_F.0).floor() as Coord, (mouse.1 * GRID_CELLS_F.1).floor() as Coord);
 								cur_char = (mouse.0 + (mouse.1 + chrmap_view) * GRID_CELLS.0) as usize;
-								cur_char = 0.max(cur_char.min(font.1.len() - 1));
+								cur_char = cur_char.min(font.chars.len() - 1);
 							}
 							if mouse_scroll != (0, 0) {
 								let dir = mouse_scroll.1.signum();
@@ -322,7 +335,7 @@ OVER THE LAZY DOG
 							}
 						}
 						
-						cur_char = 0.max(cur_char.min(font.1.len() - 1));
+						cur_char = cur_char.min(font.chars.len() - 1);
 						
 						if b4_char != cur_char {
 							let cur_row = cur_char as Coord / GRID_CELLS.0;
@@ -352,7 +365,7 @@ OVER THE LAZY DOG
 				
 				let buf = &mut buffer;
 				let font = &fonts[cur_font];
-				let ui_font = &fonts[ui_font].1;
+				let ui_font = &fonts[ui_font];
 				
 				match cur_page {
 					Page::Help => {
@@ -371,24 +384,24 @@ Map:
 Use </>/Click to select.
 Use scroll wheel to scroll.
 						";
-						draw_hershey_str(buf, ui_font, HELP_TEXT, (64, 24), 1.5, COOL_COLORS[0]);
+						draw_hershey_str(buf, ui_font, HELP_TEXT, &place((64, 24), 1.5), COOL_COLORS[0]);
 					},
 					Page::Specimen => {
-						let tooltip = format!("{}\n#{}: {} (x{:.2})", cur_page.get_name(), cur_font, font.0, font_size);
-						draw_hershey_str(buf, ui_font, &tooltip, (32, HEIGHT as Coord - 64), 1.0, COOL_COLORS[0]);
+						let details = format!("#{}: {} (x{:.2})", cur_font, font.name, font_size);
+						draw_rich_str(buf, &tooltip(ui_font, cur_page.get_name(), details), &place((32, HEIGHT as Coord - 64), 1.0));
 						
 						let specimen = if specimen.is_empty() { "Type some text..." } else { &specimen };
-						draw_hershey_str(buf, &font.1, specimen, (64, 96), font_size, COOL_COLORS[0]);
+						draw_hershey_str(buf, font, specimen, &place((64, 96), font_size), COOL_COLORS[0]);
 					},
 					Page::Map => {
-						let tooltip = format!("{}\n#{}: {}; {}", cur_page.get_name(), cur_font, font.0, cur_char);
-						draw_hershey_str(buf, ui_font, &tooltip, (32, 40), 0.75, COOL_COLORS[0]);
+						let details = format!("#{}: {}; {}", cur_font, font.name, cur_char);
+						draw_rich_str(buf, &tooltip(ui_font, cur_page.get_name(), details), &place((32, 40), 0.75));
 						
 						const CHR_SIZE: f64 = 8.0;
 						
-						let chr = &font.1[cur_char];
+						let chr = &font.chars[cur_char];
 						let middle = (CENTER.0 / 2, CENTER.1);
-						draw_hershey_char(buf, chr, middle, CHR_SIZE, COOL_COLORS[0]);
+						draw_hershey_char(buf, chr, &place(middle, CHR_SIZE), COOL_COLORS[0]);
 						
 						for j in 0..GRID_CELLS.1 {
 							for i in 0..GRID_CELLS.0 {
@@ -408,11 +421,11 @@ Use scroll wheel to scroll.
 								
 								let cpos = (ce.0.round() as Coord, ce.1.round() as Coord);
 								
-								if let Some(chr) = font.1.get(ci) {
+								if let Some(chr) = font.chars.get(ci) {
 									let is_current = ci == cur_char;
 									
 									draw_rect(buf, tl, br, COOL_COLORS[1]);
-									draw_hershey_char(buf, chr, cpos, if is_current { 1.25 } else { 1.0 }, COOL_COLORS[0]);
+									draw_hershey_char(buf, chr, &place(cpos, if is_current { 1.25 } else { 1.0 }), COOL_COLORS[0]);
 									
 									if is_current {
 										const INSET_AMT: Coord = 3;
@@ -474,8 +487,14 @@ fn vec2_to_index(a: Vec2) -> usize {
 }
 
 #[inline]
-fn v2i8_to_vec2(v: (i8, i8)) -> Vec2 {
-	(v.0 as Coord, v.1 as Coord)
+fn point_to_vec2(p: Point) -> Vec2 {
+	(p.0.round() as Coord, p.1.round() as Coord)
+}
+
+/// The old "put it at `p`, `s` times bigger" placement.
+fn place(p: Vec2, s: f64) -> Transform {
+	Transform::uniform_scale(s)
+		.then(&Transform::translate(p.0 as f64, p.1 as f64))
 }
 
 /// simply don't draw br > tl
@@ -504,46 +523,32 @@ fn draw_line(buf: &mut Box<[u32]>, a: Vec2, b: Vec2, c: u32) {
 	}
 }
 
-fn draw_hershey_char(buf: &mut Box<[u32]>, chr: &HersheyChar, p: Vec2, s: f64, c: u32) {
-	let mut pen_prev: Option<Vec2> = None;
-	
-	for v in chr.vertex_data.iter() {
-		let mut v = v.map(v2i8_to_vec2);
-		if let Some(v1) = v {
-			let v1 = (
-				p.0 + (v1.0 as f64 * s).round() as Coord,
-				p.1 + (v1.1 as f64 * s).round() as Coord
-			);
-			v = Some(v1);
-			if let Some(v2) = pen_prev {
-				draw_line(buf, v1, v2, c);
-			}
+fn draw_hershey_char(buf: &mut Box<[u32]>, chr: &HersheyChar, t: &Transform, c: u32) {
+	for stroke in chr.transformed(t) {
+		for pair in stroke.windows(2) {
+			draw_line(buf, point_to_vec2(pair[0]), point_to_vec2(pair[1]), c);
 		}
-		pen_prev = v;
 	}
 }
 
 // does it show that this was hacked together?
 // TODO: fix kerning
-fn draw_hershey_str(buf: &mut Box<[u32]>, font: &[HersheyChar], st: &str, p: Vec2, s: f64, c: u32) {
-	let mut ofs = (0, 0);
-	
-	for ch in st.bytes() {
-		if ch == b'\n' {
-			ofs = (0, ofs.1 + 32);
-			continue;
-		}
-		
-		let ch = ch.saturating_sub(b' ') as usize;
-		let ch = font.get(ch);
-		if let Some(ch) = ch {
-			let w = (ch.right_hand - ch.left_hand) as Coord;
-			let p = (
-				p.0 + ((ofs.0 - ch.left_hand as Coord) as f64 * s) as Coord,
-				p.1 + ((ofs.1 as f64) * s) as Coord
-			);
-			draw_hershey_char(buf, ch, p, s, c);
-			ofs.0 += w;
-		}
+fn draw_hershey_str<F: Glyphs + ?Sized>(buf: &mut Box<[u32]>, font: &F, st: &str, t: &Transform, c: u32) {
+	for placed in layout_str(font, st, t) {
+		draw_hershey_char(buf, placed.chr, &placed.transform, c);
 	}
 }
+
+fn draw_rich_str<F: Glyphs + ?Sized>(buf: &mut Box<[u32]>, text: &RichText<F>, t: &Transform) {
+	for rich in layout_rich(text, t) {
+		draw_hershey_char(buf, rich.placed.chr, &rich.placed.transform, rich.style.color);
+	}
+}
+
+/// The page name, then the details under it in a quieter colour.
+fn tooltip<'a>(font: &'a HersheyFont, page: &str, details: String) -> RichText<'a> {
+	let style = |color| SpanStyle { color, ..SpanStyle::default() };
+	RichText::new()
+		.with(format!("{}\n", page), font, style(COOL_COLORS[0]))
+		.with(details, font, style(COOL_COLORS[1]))
+}